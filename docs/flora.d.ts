@@ -0,0 +1,63 @@
+// Type definitions for flora widgets, generated by `flora types`.
+
+declare namespace Flora {
+    /** The bounds of a window or monitor, as returned to the page. */
+    interface Bounds {
+        height: number;
+        width: number;
+        x: number;
+        y: number;
+    }
+
+    /** Information about the widget, as returned by `flora.getInfo()`. */
+    interface Info {
+        /** The config the widget was started with, if any. */
+        configPath?: string | null;
+        /** The DPI of the window, and the scale factor it corresponds to. */
+        dpi: number;
+        monitor?: MonitorInfo | null;
+        name: string;
+        scale: number;
+    }
+
+    /** The monitor a widget is placed on, as returned to the page. */
+    interface MonitorInfo {
+        bounds: Bounds;
+        /** The device name of the monitor, e.g. `\\.\DISPLAY1`. */
+        name: string;
+        primary: boolean;
+    }
+
+    interface Api {
+        /** Hides the widget, with its transition. */
+        hide(): Promise<void>;
+        /** Shows the widget, with its transition. */
+        show(): Promise<void>;
+        /** Closes the widget, stopping its process. */
+        close(): Promise<void>;
+        /** Moves the widget, resolving with its new bounds. */
+        moveTo(x: number, y: number): Promise<Bounds>;
+        /** Resizes the widget, resolving with its new bounds. */
+        resizeTo(width: number, height: number): Promise<Bounds>;
+        /** Sets the opacity of the widget, from 0.0 to 1.0. */
+        setOpacity(opacity: number): Promise<void>;
+        /** Resolves with the bounds of the widget. */
+        getBounds(): Promise<Bounds>;
+        /** Resolves with the name, config path, monitor and DPI of the widget. */
+        getInfo(): Promise<Info>;
+        storage: {
+            /** Resolves with the stored value of `key`, or `null` if there is none. */
+            get(key: string): Promise<unknown>;
+            /** Stores a JSON value under `key`, replacing any value stored before. */
+            set(key: string, value: unknown): Promise<void>;
+            /** Deletes the value of `key`, resolving with whether there was one. */
+            delete(key: string): Promise<boolean>;
+            /** Resolves with the keys of all stored values, in order. */
+            list(): Promise<string[]>;
+        };
+        /** The params of the widget config, updated along with a `flora:params` event. */
+        params: Record<string, unknown>;
+    }
+}
+
+declare var flora: Flora.Api;
@@ -0,0 +1,281 @@
+//! Animates the opacity and position of the widget window, e.g. when it is shown or hidden.
+//!
+//! Animations are driven by a timer on the window thread, but everything here is pure: an
+//! [`Animation`] only describes how the window looks a given time after it started.
+use std::time::Duration;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::layout::Rect;
+
+/// How long transitions take when no duration is configured, in milliseconds.
+pub const DEFAULT_TRANSITION_DURATION: u32 = 150;
+
+/// How the widget appears when it is shown, and disappears when it is hidden.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transition {
+    /// The widget appears and disappears instantly.
+    #[default]
+    None,
+    /// The widget fades in and out.
+    Fade,
+    /// The widget fades in while sliding in from the closest edge of the monitor, and back out
+    /// again.
+    Slide,
+}
+
+/// Options that apply while the cursor is over the widget.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub struct Hover {
+    /// The opacity of the widget while the cursor is over it, from 0.0 to 1.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opacity: Option<f64>,
+}
+
+/// How the progress of an animation maps to the progress of the animated values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    /// Starts slowly and speeds up, for things leaving.
+    In,
+    /// Starts quickly and slows down, for things arriving.
+    Out,
+    /// Starts and ends slowly.
+    InOut,
+}
+
+impl Easing {
+    /// Eases the progress `t`, from 0.0 to 1.0. Progress outside that range is clamped.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::In => t.powi(3),
+            Self::Out => 1.0 - (1.0 - t).powi(3),
+            Self::InOut if t < 0.5 => 4.0 * t.powi(3),
+            Self::InOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+        }
+    }
+}
+
+/// Interpolates between `from` and `to`, where `t` is the progress from 0.0 to 1.0.
+pub fn lerp(from: f64, to: f64, t: f64) -> f64 {
+    from + (to - from) * t
+}
+
+/// The animated state of the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    /// The opacity of the window, from 0.0 to 1.0.
+    pub opacity: f64,
+    /// The offset of the window from where it is laid out, in pixels.
+    pub offset: (f64, f64),
+}
+
+impl Frame {
+    /// A frame at the given opacity, where the window is where it is laid out.
+    pub fn at_rest(opacity: f64) -> Frame {
+        Frame {
+            opacity,
+            offset: (0.0, 0.0),
+        }
+    }
+
+    /// Interpolates between this frame and `to`, where `t` is the progress from 0.0 to 1.0.
+    pub fn lerp(&self, to: &Frame, t: f64) -> Frame {
+        Frame {
+            opacity: lerp(self.opacity, to.opacity, t),
+            offset: (
+                lerp(self.offset.0, to.offset.0, t),
+                lerp(self.offset.1, to.offset.1, t),
+            ),
+        }
+    }
+
+    /// The offset of the window in whole pixels.
+    pub fn pixel_offset(&self) -> (i32, i32) {
+        (self.offset.0.round() as i32, self.offset.1.round() as i32)
+    }
+}
+
+/// An animation from one frame to another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Animation {
+    pub from: Frame,
+    pub to: Frame,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Animation {
+    /// Returns the frame `elapsed` after the animation started.
+    pub fn frame_at(&self, elapsed: Duration) -> Frame {
+        if self.is_finished(elapsed) {
+            return self.to;
+        }
+
+        let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+        self.from.lerp(&self.to, self.easing.apply(t))
+    }
+
+    /// Whether the animation has reached its last frame `elapsed` after it started.
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        elapsed >= self.duration
+    }
+}
+
+impl Transition {
+    /// Returns the frame the widget is shown from and hidden to, where `rect` is where the widget
+    /// is laid out on `monitor`.
+    pub fn hidden_frame(&self, rect: &Rect, monitor: &Rect) -> Frame {
+        match self {
+            Self::None | Self::Fade => Frame::at_rest(0.0),
+            Self::Slide => Frame {
+                opacity: 0.0,
+                offset: slide_offset(rect, monitor),
+            },
+        }
+    }
+}
+
+/// Returns the offset a widget laid out at `rect` slides in from, which is its own size towards
+/// the closest edge of the monitor.
+pub fn slide_offset(rect: &Rect, monitor: &Rect) -> (f64, f64) {
+    let (width, height) = (rect.width as f64, rect.height as f64);
+    let edges = [
+        (rect.x - monitor.x, (-width, 0.0)),
+        (
+            monitor.x + monitor.width - (rect.x + rect.width),
+            (width, 0.0),
+        ),
+        (rect.y - monitor.y, (0.0, -height)),
+        (
+            monitor.y + monitor.height - (rect.y + rect.height),
+            (0.0, height),
+        ),
+    ];
+
+    edges
+        .into_iter()
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, offset)| offset)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::layout::Rect;
+
+    use super::{lerp, slide_offset, Animation, Easing, Frame, Transition};
+
+    const MONITOR: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+
+    #[test]
+    fn test_easing() {
+        for easing in [Easing::In, Easing::Out, Easing::InOut] {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+            assert_eq!(easing.apply(-1.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(2.0), 1.0, "{easing:?}");
+
+            // easing never goes backwards
+            let steps: Vec<f64> = (0..=20).map(|i| easing.apply(i as f64 / 20.0)).collect();
+            assert!(steps.windows(2).all(|w| w[0] <= w[1]), "{easing:?}");
+        }
+
+        assert!(Easing::In.apply(0.5) < 0.5);
+        assert!(Easing::Out.apply(0.5) > 0.5);
+        assert_eq!(Easing::InOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(lerp(0.0, 10.0, 0.5), 5.0);
+        assert_eq!(lerp(1.0, 0.25, 1.0), 0.25);
+
+        let from = Frame {
+            opacity: 0.0,
+            offset: (200.0, -20.0),
+        };
+        assert_eq!(
+            from.lerp(&Frame::at_rest(1.0), 0.25),
+            Frame {
+                opacity: 0.25,
+                offset: (150.0, -15.0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_animation_frames() {
+        let animation = Animation {
+            from: Frame::at_rest(0.2),
+            to: Frame::at_rest(1.0),
+            duration: Duration::from_millis(200),
+            easing: Easing::InOut,
+        };
+
+        assert_eq!(animation.frame_at(Duration::ZERO), Frame::at_rest(0.2));
+        let halfway = animation.frame_at(Duration::from_millis(100));
+        assert!((halfway.opacity - 0.6).abs() < 1e-9);
+        assert!(!animation.is_finished(Duration::from_millis(199)));
+        assert!(animation.is_finished(Duration::from_millis(200)));
+        assert_eq!(
+            animation.frame_at(Duration::from_secs(1)),
+            Frame::at_rest(1.0)
+        );
+
+        // animations without a duration finish immediately
+        let instant = Animation {
+            duration: Duration::ZERO,
+            ..animation
+        };
+        assert!(instant.is_finished(Duration::ZERO));
+        assert_eq!(instant.frame_at(Duration::ZERO), Frame::at_rest(1.0));
+    }
+
+    #[test]
+    fn test_slide_from_closest_edge() {
+        let top_right = Rect {
+            x: 1710,
+            y: 4,
+            width: 200,
+            height: 20,
+        };
+        assert_eq!(slide_offset(&top_right, &MONITOR), (0.0, -20.0));
+
+        let right = Rect {
+            x: 1710,
+            y: 500,
+            width: 200,
+            height: 20,
+        };
+        assert_eq!(slide_offset(&right, &MONITOR), (200.0, 0.0));
+
+        let bottom = Rect {
+            x: 860,
+            y: 1040,
+            width: 200,
+            height: 30,
+        };
+        assert_eq!(
+            Transition::Slide.hidden_frame(&bottom, &MONITOR),
+            Frame {
+                opacity: 0.0,
+                offset: (0.0, 30.0),
+            }
+        );
+        assert_eq!(
+            Transition::Fade.hidden_frame(&bottom, &MONITOR),
+            Frame::at_rest(0.0)
+        );
+    }
+}
@@ -0,0 +1,488 @@
+//! The `window.flora` JavaScript API, which lets a widget control its own window and keep state
+//! across restarts.
+//!
+//! [`API_SCRIPT`] defines the API on top of built-in bindings, which [`bind`] binds to the window
+//! when it is created. Bounds are in physical pixels in virtual screen coordinates, the same as
+//! `flora list`.
+use anyhow::{anyhow, bail, Result};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tracing::info;
+
+use crate::{
+    binding::{AsyncBinding, Registry},
+    dpi,
+    layout::Rect,
+    monitor::Monitor,
+    storage::{self, Storage},
+    window::FloraWindow,
+    windows_api,
+};
+
+/// The name of the binding hiding the window.
+pub const HIDE_BINDING: &str = "__floraHide";
+/// The name of the binding showing the window.
+pub const SHOW_BINDING: &str = "__floraShow";
+/// The name of the binding closing the window, which stops the widget.
+pub const CLOSE_BINDING: &str = "__floraClose";
+/// The name of the binding moving the window.
+pub const MOVE_TO_BINDING: &str = "__floraMoveTo";
+/// The name of the binding resizing the window.
+pub const RESIZE_TO_BINDING: &str = "__floraResizeTo";
+/// The name of the binding setting the opacity of the window.
+pub const SET_OPACITY_BINDING: &str = "__floraSetOpacity";
+/// The name of the binding returning the bounds of the window.
+pub const GET_BOUNDS_BINDING: &str = "__floraGetBounds";
+/// The name of the binding returning information about the widget.
+pub const GET_INFO_BINDING: &str = "__floraGetInfo";
+/// The name of the binding reading an entry from the storage of the widget.
+pub const STORAGE_GET_BINDING: &str = "__floraStorageGet";
+/// The name of the binding writing an entry into the storage of the widget.
+pub const STORAGE_SET_BINDING: &str = "__floraStorageSet";
+/// The name of the binding deleting an entry from the storage of the widget.
+pub const STORAGE_DELETE_BINDING: &str = "__floraStorageDelete";
+/// The name of the binding returning the keys of the storage of the widget.
+pub const STORAGE_LIST_BINDING: &str = "__floraStorageList";
+
+/// A function of `window.flora`, and the binding behind it.
+pub struct Function {
+    /// The path of the function from `window.flora`, e.g. `storage.get`.
+    pub name: &'static str,
+    pub binding: &'static str,
+    /// The names of the parameters, in order.
+    pub params: &'static [&'static str],
+    pub description: &'static str,
+}
+
+/// The functions of `window.flora`, in the order they are documented.
+pub const FUNCTIONS: [Function; 12] = [
+    Function {
+        name: "hide",
+        binding: HIDE_BINDING,
+        params: &[],
+        description: "Hides the widget, with its transition.",
+    },
+    Function {
+        name: "show",
+        binding: SHOW_BINDING,
+        params: &[],
+        description: "Shows the widget, with its transition.",
+    },
+    Function {
+        name: "close",
+        binding: CLOSE_BINDING,
+        params: &[],
+        description: "Closes the widget, stopping its process.",
+    },
+    Function {
+        name: "moveTo",
+        binding: MOVE_TO_BINDING,
+        params: &["x", "y"],
+        description: "Moves the widget, resolving with its new bounds.",
+    },
+    Function {
+        name: "resizeTo",
+        binding: RESIZE_TO_BINDING,
+        params: &["width", "height"],
+        description: "Resizes the widget, resolving with its new bounds.",
+    },
+    Function {
+        name: "setOpacity",
+        binding: SET_OPACITY_BINDING,
+        params: &["opacity"],
+        description: "Sets the opacity of the widget, from 0.0 to 1.0.",
+    },
+    Function {
+        name: "getBounds",
+        binding: GET_BOUNDS_BINDING,
+        params: &[],
+        description: "Resolves with the bounds of the widget.",
+    },
+    Function {
+        name: "getInfo",
+        binding: GET_INFO_BINDING,
+        params: &[],
+        description: "Resolves with the name, config path, monitor and DPI of the widget.",
+    },
+    Function {
+        name: "storage.get",
+        binding: STORAGE_GET_BINDING,
+        params: &["key"],
+        description: "Resolves with the stored value of `key`, or `null` if there is none.",
+    },
+    Function {
+        name: "storage.set",
+        binding: STORAGE_SET_BINDING,
+        params: &["key", "value"],
+        description: "Stores a JSON value under `key`, replacing any value stored before.",
+    },
+    Function {
+        name: "storage.delete",
+        binding: STORAGE_DELETE_BINDING,
+        params: &["key"],
+        description: "Deletes the value of `key`, resolving with whether there was one.",
+    },
+    Function {
+        name: "storage.list",
+        binding: STORAGE_LIST_BINDING,
+        params: &[],
+        description: "Resolves with the keys of all stored values, in order.",
+    },
+];
+
+/// What the bindings behind `window.flora` are bound to: the window, or a [`Registry`] recording
+/// their signatures.
+pub trait Binder {
+    fn bind_function<F: AsyncBinding<Args>, Args>(&mut self, name: &str, f: F) -> Result<()>;
+}
+
+impl Binder for Registry {
+    fn bind_function<F: AsyncBinding<Args>, Args>(&mut self, name: &str, _f: F) -> Result<()> {
+        self.register_async::<F, Args>(name);
+        Ok(())
+    }
+}
+
+/// Binds the functions behind `window.flora`.
+pub fn bind(binder: &mut impl Binder) -> Result<()> {
+    binder.bind_function(HIDE_BINDING, hide)?;
+    binder.bind_function(SHOW_BINDING, show)?;
+    binder.bind_function(CLOSE_BINDING, close)?;
+    binder.bind_function(MOVE_TO_BINDING, move_to)?;
+    binder.bind_function(RESIZE_TO_BINDING, resize_to)?;
+    binder.bind_function(SET_OPACITY_BINDING, set_opacity)?;
+    binder.bind_function(GET_BOUNDS_BINDING, get_bounds)?;
+    binder.bind_function(GET_INFO_BINDING, get_info)?;
+    binder.bind_function(STORAGE_GET_BINDING, storage_get)?;
+    binder.bind_function(STORAGE_SET_BINDING, storage_set)?;
+    binder.bind_function(STORAGE_DELETE_BINDING, storage_delete)?;
+    binder.bind_function(STORAGE_LIST_BINDING, storage_list)
+}
+
+/// Records the signatures of the bindings behind `window.flora`, so the API can be described
+/// without a window.
+pub fn register(registry: &mut Registry) {
+    bind(registry).expect("registering a binding can't fail");
+}
+
+/// Runs `f` on the window thread, resolving with its result. Most functions act on the window this
+/// way, since showing, hiding, moving and resizing it can't happen while handling a message from
+/// the page.
+async fn on_window<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(FloraWindow) -> Result<T> + Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    crate::execute(move |webview| {
+        let _ = tx.send(f(webview));
+    })?;
+
+    rx.await
+        .map_err(|_| anyhow!("the window closed before the call ran"))?
+}
+
+async fn hide() -> Result<()> {
+    on_window(|webview| {
+        webview.hide()?;
+        Ok(())
+    })
+    .await
+}
+
+async fn show() -> Result<()> {
+    on_window(|webview| {
+        webview.show()?;
+        Ok(())
+    })
+    .await
+}
+
+async fn close() -> Result<()> {
+    info!("closing window at the request of the page");
+    on_window(|webview| webview.close()).await
+}
+
+/// Places the window at the rect `f` makes of the rect it occupies, resolving with the bounds it
+/// was placed at.
+async fn place<F>(f: F) -> Result<Bounds>
+where
+    F: FnOnce(Rect) -> Rect + Send + 'static,
+{
+    on_window(move |webview| Ok(Bounds::from(webview.place(f(crate::get_window_rect()))?))).await
+}
+
+async fn move_to(x: f64, y: f64) -> Result<Bounds> {
+    let (x, y) = to_point((x, y))?;
+    place(move |rect| Rect { x, y, ..rect }).await
+}
+
+async fn resize_to(width: f64, height: f64) -> Result<Bounds> {
+    let (width, height) = to_size((width, height))?;
+    place(move |rect| Rect {
+        width,
+        height,
+        ..rect
+    })
+    .await
+}
+
+async fn set_opacity(opacity: f64) -> Result<()> {
+    let alpha = to_alpha(opacity)?;
+    on_window(move |webview| {
+        webview.set_opacity(alpha)?;
+        Ok(())
+    })
+    .await
+}
+
+async fn get_bounds() -> Result<Bounds> {
+    on_window(|webview| {
+        Ok(Bounds::from(windows_api::get_window_bounds(
+            webview.get_window(),
+        )?))
+    })
+    .await
+}
+
+async fn get_info() -> Result<Info> {
+    let dpi = on_window(|webview| Ok(windows_api::get_window_dpi(webview.get_window()))).await?;
+
+    Ok(Info {
+        name: crate::NAME.lock().unwrap().clone(),
+        config_path: crate::CONFIG_PATH
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|path| path.display().to_string()),
+        monitor: crate::find_target_monitor().as_ref().map(MonitorInfo::from),
+        dpi,
+        scale: dpi::scale_factor(dpi),
+    })
+}
+
+/// Runs `f` on the storage of the widget, on the blocking pool so the file I/O holds up neither the
+/// window thread nor the runtime. The storage is opened again on every call, since
+/// `flora storage` may change it meanwhile, and locked until `f` returns.
+async fn on_storage<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce(Storage) -> Result<T> + Send + 'static,
+{
+    let name = crate::NAME.lock().unwrap().clone();
+    tokio::task::spawn_blocking(move || storage::with_storage(&name, f)).await?
+}
+
+async fn storage_get(key: String) -> Result<Option<Value>> {
+    on_storage(move |storage| Ok(storage.get(&key).cloned())).await
+}
+
+async fn storage_set(key: String, value: Value) -> Result<()> {
+    on_storage(move |mut storage| storage.set(key, value)).await
+}
+
+async fn storage_delete(key: String) -> Result<bool> {
+    on_storage(move |mut storage| storage.delete(&key)).await
+}
+
+async fn storage_list() -> Result<Vec<String>> {
+    on_storage(|storage| Ok(storage.keys())).await
+}
+
+/// Defines `window.flora`, where every function returns a promise resolving once the binding
+/// behind it has run.
+pub const API_SCRIPT: &str = r#"
+(function() {
+    function binding(name) {
+        return function() {
+            return window[name].apply(window, arguments);
+        };
+    }
+
+    var flora = window.flora = window.flora || {};
+    flora.hide = binding("__floraHide");
+    flora.show = binding("__floraShow");
+    flora.close = binding("__floraClose");
+    flora.moveTo = binding("__floraMoveTo");
+    flora.resizeTo = binding("__floraResizeTo");
+    flora.setOpacity = binding("__floraSetOpacity");
+    flora.getBounds = binding("__floraGetBounds");
+    flora.getInfo = binding("__floraGetInfo");
+    flora.storage = {};
+    flora.storage.get = binding("__floraStorageGet");
+    flora.storage.set = binding("__floraStorageSet");
+    flora.storage.delete = binding("__floraStorageDelete");
+    flora.storage.list = binding("__floraStorageList");
+})();
+"#;
+
+/// The bounds of a window or monitor, as returned to the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Bounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl From<Rect> for Bounds {
+    fn from(rect: Rect) -> Self {
+        Bounds {
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+        }
+    }
+}
+
+/// The monitor a widget is placed on, as returned to the page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct MonitorInfo {
+    /// The device name of the monitor, e.g. `\\.\DISPLAY1`.
+    pub name: String,
+    pub bounds: Bounds,
+    pub primary: bool,
+}
+
+impl From<&Monitor> for MonitorInfo {
+    fn from(monitor: &Monitor) -> Self {
+        MonitorInfo {
+            name: monitor.name.clone(),
+            bounds: monitor.bounds.into(),
+            primary: monitor.primary,
+        }
+    }
+}
+
+/// Information about the widget, as returned by `flora.getInfo()`.
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Info {
+    pub name: String,
+    /// The config the widget was started with, if any.
+    pub config_path: Option<String>,
+    pub monitor: Option<MonitorInfo>,
+    /// The DPI of the window, and the scale factor it corresponds to.
+    pub dpi: u32,
+    pub scale: f64,
+}
+
+/// Converts an opacity passed by the page, from 0.0 to 1.0, into an alpha value.
+pub fn to_alpha(opacity: f64) -> Result<u8> {
+    if !(0.0..=1.0).contains(&opacity) {
+        bail!("opacity must be between 0.0 and 1.0, but was {opacity}");
+    }
+
+    Ok((opacity * u8::MAX as f64).round() as u8)
+}
+
+/// Converts a point passed by the page into whole pixels.
+pub fn to_point((x, y): (f64, f64)) -> Result<(i32, i32)> {
+    if !x.is_finite() || !y.is_finite() {
+        bail!("the position must be finite, but was ({x}, {y})");
+    }
+
+    Ok((x.round() as i32, y.round() as i32))
+}
+
+/// Converts a size passed by the page into whole pixels.
+pub fn to_size((width, height): (f64, f64)) -> Result<(i32, i32)> {
+    if !(width.is_finite() && width >= 1.0 && height.is_finite() && height >= 1.0) {
+        bail!("the size must be at least 1x1, but was {width}x{height}");
+    }
+
+    Ok((width.round() as i32, height.round() as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::layout::Rect;
+
+    use crate::binding::Registry;
+
+    use super::{
+        register, to_alpha, to_point, to_size, Bounds, Info, MonitorInfo, API_SCRIPT, FUNCTIONS,
+    };
+
+    #[test]
+    fn test_functions() {
+        let mut registry = Registry::default();
+        register(&mut registry);
+        assert_eq!(registry.signatures().count(), FUNCTIONS.len());
+
+        for function in FUNCTIONS {
+            let line = format!(
+                "flora.{} = binding(\"{}\");",
+                function.name, function.binding
+            );
+            assert!(API_SCRIPT.contains(&line), "{line}");
+
+            let signature = registry
+                .signatures()
+                .find(|signature| signature.name == function.binding)
+                .unwrap();
+            assert_eq!(
+                signature.params.as_ref().unwrap().len(),
+                function.params.len(),
+                "{signature}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(to_alpha(0.0).unwrap(), 0);
+        assert_eq!(to_alpha(0.5).unwrap(), 128);
+        assert_eq!(to_alpha(1.0).unwrap(), 255);
+        assert!(to_alpha(1.5).is_err());
+        assert!(to_alpha(f64::NAN).is_err());
+
+        assert_eq!(to_point((-10.4, 20.6)).unwrap(), (-10, 21));
+        assert!(to_point((f64::INFINITY, 0.0)).is_err());
+
+        assert_eq!(to_size((200.0, 20.5)).unwrap(), (200, 21));
+        assert!(to_size((0.0, 20.0)).is_err());
+        assert!(to_size((200.0, f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_serialize_info() {
+        let bounds = Rect {
+            x: 1920,
+            y: 0,
+            width: 2560,
+            height: 1440,
+        };
+        let info = Info {
+            name: "clock".to_string(),
+            config_path: None,
+            monitor: Some(MonitorInfo {
+                name: r"\\.\DISPLAY2".to_string(),
+                bounds: Bounds::from(bounds),
+                primary: false,
+            }),
+            dpi: 144,
+            scale: 1.5,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&info).unwrap(),
+            json!({
+                "name": "clock",
+                "configPath": null,
+                "monitor": {
+                    "name": r"\\.\DISPLAY2",
+                    "bounds": { "x": 1920, "y": 0, "width": 2560, "height": 1440 },
+                    "primary": false,
+                },
+                "dpi": 144,
+                "scale": 1.5,
+            })
+        );
+    }
+}
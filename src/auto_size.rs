@@ -0,0 +1,90 @@
+//! Sizes the widget window to its content.
+//!
+//! When a dimension is `auto`, [`auto_size_script`] observes the size of the root element of the
+//! page and reports it whenever it changes. The reported size replaces the content size in the
+//! layout, and the window is resolved again, which keeps it anchored and within its limits. The
+//! content size is kept in DIPs, so it stays right when the window moves to a monitor with another
+//! DPI, where the page doesn't report it again since its size in CSS pixels is unchanged.
+
+use crate::dpi;
+
+/// The name of the binding the page reports its size through.
+pub const CONTENT_SIZE_BINDING: &str = "__floraSetContentSize";
+
+/// Returns the script that reports the size of the page, in CSS pixels along with the device
+/// pixel ratio. With an auto width, the root element is laid out at its natural width instead of
+/// the width of the window, so the content can shrink as well as grow.
+pub fn auto_size_script(auto_width: bool) -> String {
+    let css = match auto_width {
+        true => ":where(html) { width: max-content; overflow: hidden; }",
+        false => ":where(html) { overflow: hidden; }",
+    };
+    let css = serde_json::to_string(css).unwrap();
+
+    format!(
+        r#"
+(function() {{
+    document.addEventListener("DOMContentLoaded", function() {{
+        var style = document.createElement("style");
+        style.textContent = {css};
+        document.head.prepend(style);
+
+        var root = document.documentElement;
+        new ResizeObserver(function() {{
+            var rect = root.getBoundingClientRect();
+            window.__floraSetContentSize(rect.width, rect.height, window.devicePixelRatio);
+        }}).observe(root);
+    }});
+}})();
+"#
+    )
+}
+
+/// Converts a size reported by the page, in CSS pixels, into DIPs, where `dpi` is the DPI of the
+/// monitor the page was measured on. The device pixel ratio also covers the zoom of the page.
+pub fn to_dips(width: f64, height: f64, device_pixel_ratio: f64, dpi: u32) -> (f64, f64) {
+    let scale = device_pixel_ratio / dpi::scale_factor(dpi);
+
+    (width * scale, height * scale)
+}
+
+/// Converts a content size in DIPs into physical pixels on a monitor with the given DPI. Sizes are
+/// rounded up so the content is never clipped, with some tolerance for floating point error.
+pub fn to_physical_size((width, height): (f64, f64), dpi: u32) -> (i32, i32) {
+    let scale = dpi::scale_factor(dpi);
+    let to_physical = |value: f64| (value * scale - 0.01).ceil().max(0.0) as i32;
+
+    (to_physical(width), to_physical(height))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dpi::DEFAULT_DPI;
+
+    use super::{to_dips, to_physical_size};
+
+    fn measure(width: f64, height: f64, device_pixel_ratio: f64, dpi: u32) -> (i32, i32) {
+        to_physical_size(to_dips(width, height, device_pixel_ratio, dpi), dpi)
+    }
+
+    #[test]
+    fn test_to_physical_size() {
+        assert_eq!(measure(200.0, 20.0, 1.0, DEFAULT_DPI), (200, 20));
+        assert_eq!(measure(200.4, 20.0, 1.5, 144), (301, 30));
+        // floating point error does not add a pixel
+        assert_eq!(measure(0.1 + 0.2, 10.0 / 3.0, 30.0, DEFAULT_DPI), (9, 100));
+        assert_eq!(measure(0.0, 0.0, 2.0, 192), (0, 0));
+    }
+
+    #[test]
+    fn test_dpi_change() {
+        // measured at 150% scale, then moved to a monitor at 200% scale
+        let size = to_dips(200.0, 20.0, 1.5, 144);
+        assert_eq!(to_physical_size(size, 192), (400, 40));
+
+        // a zoomed page keeps its zoom
+        let size = to_dips(200.0, 20.0, 1.5 * 1.25, 144);
+        assert_eq!(size, (250.0, 25.0));
+        assert_eq!(to_physical_size(size, DEFAULT_DPI), (250, 25));
+    }
+}
@@ -0,0 +1,773 @@
+//! Typed bindings, which let Rust functions be called from the page.
+//!
+//! A page calls a binding with positional JSON arguments. [`Binding`] is implemented for functions
+//! with up to six arguments that implement [`Deserialize`], returning a value that implements
+//! [`Serialize`], so bindings can be declared as ordinary functions:
+//!
+//! ```ignore
+//! window.bind_typed("add", |a: f64, b: f64| Ok(a + b))?;
+//! ```
+//!
+//! [`AsyncBinding`] is the same for functions returning a future, which the window runs on a Tokio
+//! runtime, so slow bindings don't block the window thread:
+//!
+//! ```ignore
+//! window.bind_async("fetch", |url: String| async move { fetch(&url).await })?;
+//! ```
+//!
+//! [`StreamBinding`] is for functions that send any number of items to the page through a [`Sink`]
+//! passed as their last argument, which the page reads as an async iterator. The stream ends when
+//! the function returns:
+//!
+//! ```ignore
+//! window.bind_stream("tail", |path: String, sink: Sink<String>| async move {
+//!     let mut lines = follow(&path).await?;
+//!     while let Some(line) = lines.next().await {
+//!         sink.send(line?).await?;
+//!     }
+//!     Ok(())
+//! })?;
+//! ```
+//!
+//! Arguments are decoded one at a time, so errors point at the argument that is wrong. Every
+//! binding is recorded in a [`Registry`], along with the JSON schemas of its arguments and return
+//! value.
+//!
+//! Calls that fail are rejected with an [`RpcError`], which the page receives as a
+//! `{code, message, data}` object.
+use std::{
+    collections::BTreeMap, error::Error, fmt, future::Future, marker::PhantomData, pin::Pin,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema, Map};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::Semaphore;
+
+/// How long calls wait for a result before they are rejected, in milliseconds.
+pub const DEFAULT_RPC_TIMEOUT: u32 = 30_000;
+
+/// How many items a stream sends ahead of the page, before waiting for the page to consume them.
+pub const STREAM_CAPACITY: usize = 16;
+
+/// A function that can be called from the page with positional JSON arguments, where `Args` is
+/// the tuple of its argument types.
+pub trait Binding<Args>: 'static {
+    /// Decodes the arguments, calls the function, and encodes its return value. `name` is the name
+    /// of the binding, used in errors.
+    fn call(&mut self, name: &str, params: Vec<Value>) -> Result<Value>;
+
+    /// Returns the signature of the binding, adding the schemas of its types to `generator`.
+    fn signature(name: &str, generator: &mut SchemaGenerator) -> Signature
+    where
+        Self: Sized;
+}
+
+/// The future of an asynchronous binding, resolving to its encoded return value.
+pub type BindingFuture = Pin<Box<dyn Future<Output = Result<Value>> + Send>>;
+
+/// An asynchronous function that can be called from the page with positional JSON arguments,
+/// where `Args` is the tuple of its argument types. The arguments are decoded before the
+/// function is called, so invalid arguments reject the call without running anything.
+pub trait AsyncBinding<Args>: 'static {
+    /// Decodes the arguments, and calls the function. `name` is the name of the binding, used in
+    /// errors.
+    fn call(&mut self, name: &str, params: Vec<Value>) -> Result<BindingFuture>;
+
+    /// Returns the signature of the binding, adding the schemas of its types to `generator`.
+    fn signature(name: &str, generator: &mut SchemaGenerator) -> Signature
+    where
+        Self: Sized;
+}
+
+/// A streaming function that can be called from the page with positional JSON arguments, where
+/// `Args` is the tuple of its argument types and `T` the type of the items it sends.
+pub trait StreamBinding<Args, T>: 'static {
+    /// Decodes the arguments, and calls the function with a [`Sink`] sending through `sender`.
+    /// `name` is the name of the binding, used in errors.
+    fn call(
+        &mut self,
+        name: &str,
+        params: Vec<Value>,
+        sender: StreamSender,
+    ) -> Result<BindingFuture>;
+
+    /// Returns the signature of the binding, adding the schemas of its types to `generator`.
+    fn signature(name: &str, generator: &mut SchemaGenerator) -> Signature
+    where
+        Self: Sized;
+}
+
+/// Delivers the items of a streaming call to the page. Every item takes a credit, which the page
+/// gives back once it has consumed the item, so a slow page holds the stream back instead of items
+/// piling up.
+#[derive(Clone)]
+pub struct StreamSender {
+    credit: Arc<Semaphore>,
+    deliver: Arc<dyn Fn(Value) -> Result<()> + Send + Sync>,
+}
+
+impl StreamSender {
+    pub fn new<F>(credit: Arc<Semaphore>, deliver: F) -> Self
+    where
+        F: Fn(Value) -> Result<()> + Send + Sync + 'static,
+    {
+        StreamSender {
+            credit,
+            deliver: Arc::new(deliver),
+        }
+    }
+
+    async fn send(&self, item: Value) -> Result<()> {
+        self.credit
+            .acquire()
+            .await
+            .map_err(|_| anyhow!("the stream was closed"))?
+            .forget();
+        (self.deliver)(item)
+    }
+}
+
+/// Gives a stream `count` credits back, once the page has consumed as many items. A stream never
+/// holds more than [`STREAM_CAPACITY`] credits, so a page giving back more than it consumed can't
+/// overflow the semaphore.
+pub fn add_credit(credit: &Semaphore, count: usize) {
+    let missing = STREAM_CAPACITY.saturating_sub(credit.available_permits());
+    credit.add_permits(count.min(missing));
+}
+
+/// Sends items of type `T` from a streaming binding to the page.
+pub struct Sink<T> {
+    sender: StreamSender,
+    item: PhantomData<fn(T)>,
+}
+
+impl<T: Serialize> Sink<T> {
+    /// Sends an item to the page, first waiting until the page has room for it. Fails once the
+    /// page stopped reading the stream.
+    pub async fn send(&self, item: T) -> Result<()> {
+        self.sender.send(serde_json::to_value(item)?).await
+    }
+}
+
+/// An error a call is rejected with. The codes follow JSON-RPC, and `data` carries details about the
+/// error, if any.
+///
+/// Bindings can return an `RpcError` to reject with their own code or data. Any other error is
+/// rejected as a [`RpcError::BINDING_ERROR`], with the whole chain of causes as the message.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    /// The message from the page isn't a valid call.
+    pub const INVALID_REQUEST: i32 = -32600;
+    /// No function is bound under the name that was called.
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    /// The arguments of the call couldn't be decoded.
+    pub const INVALID_PARAMS: i32 = -32602;
+    /// The binding itself returned an error.
+    pub const BINDING_ERROR: i32 = -32000;
+    /// The storage of the widget would grow beyond its quota. -32001 is taken by timeouts, which
+    /// the page rejects calls with itself.
+    pub const QUOTA_EXCEEDED: i32 = -32002;
+
+    pub fn new(code: i32, message: impl Into<String>, data: Option<Value>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data,
+        }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for RpcError {}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast_ref::<RpcError>() {
+            Some(err) => err.clone(),
+            None => RpcError::new(RpcError::BINDING_ERROR, format!("{err:#}"), None),
+        }
+    }
+}
+
+/// Checks that no more than `count` arguments were given, and pairs each argument with its index.
+/// Missing arguments are `None`.
+fn positional(
+    name: &str,
+    count: usize,
+    params: Vec<Value>,
+) -> Result<impl Iterator<Item = (usize, Option<Value>)>> {
+    if params.len() > count {
+        let message = format!(
+            "`{name}` takes {count} {}, but {} were given",
+            plural(count, "argument"),
+            params.len()
+        );
+        return Err(RpcError::new(RpcError::INVALID_PARAMS, message, None).into());
+    }
+
+    Ok(params
+        .into_iter()
+        .map(Some)
+        .chain(std::iter::repeat_with(|| None))
+        .enumerate())
+}
+
+/// Decodes the argument at `index`, if it was given. Missing arguments are decoded from `null`,
+/// the same as `undefined`, so optional arguments can be left out.
+fn decode<T: DeserializeOwned>(
+    name: &str,
+    count: usize,
+    (index, param): (usize, Option<Value>),
+) -> Result<T> {
+    let given = param.is_some();
+
+    serde_json::from_value(param.unwrap_or_default()).map_err(|e| {
+        let message = match given {
+            true => format!("invalid argument {} of `{name}`: {e}", index + 1),
+            false => format!(
+                "`{name}` takes {count} {}, but argument {} is missing",
+                plural(count, "argument"),
+                index + 1
+            ),
+        };
+        let data = json!({ "argument": index + 1 });
+        RpcError::new(RpcError::INVALID_PARAMS, message, Some(data)).into()
+    })
+}
+
+fn plural(count: usize, word: &str) -> String {
+    match count {
+        1 => word.to_string(),
+        _ => format!("{word}s"),
+    }
+}
+
+/// Decodes the arguments of a call into variables named after the argument types.
+macro_rules! decode_args {
+    ($name:ident, $params:ident, $($arg:ident),*) => {
+        let args: &[&str] = &[$(stringify!($arg)),*];
+        let count = args.len();
+        let mut $params = positional($name, count, $params)?;
+        $(let $arg: $arg = decode($name, count, $params.next().unwrap_or_default())?;)*
+    };
+}
+
+macro_rules! impl_binding {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg,)*> Binding<($($arg,)*)> for F
+        where
+            F: FnMut($($arg),*) -> Result<R> + 'static,
+            R: Serialize + JsonSchema,
+            $($arg: DeserializeOwned + JsonSchema,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(&mut self, name: &str, params: Vec<Value>) -> Result<Value> {
+                decode_args!(name, params, $($arg),*);
+                Ok(serde_json::to_value(self($($arg),*)?)?)
+            }
+
+            fn signature(name: &str, generator: &mut SchemaGenerator) -> Signature {
+                Signature {
+                    name: name.to_string(),
+                    params: Some(vec![$(Type::of::<$arg>(generator)),*]),
+                    returns: Some(Type::of::<R>(generator)),
+                    stream: false,
+                }
+            }
+        }
+
+        impl<F, Fut, R, $($arg,)*> AsyncBinding<($($arg,)*)> for F
+        where
+            F: FnMut($($arg),*) -> Fut + 'static,
+            Fut: Future<Output = Result<R>> + Send + 'static,
+            R: Serialize + JsonSchema,
+            $($arg: DeserializeOwned + JsonSchema,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(&mut self, name: &str, params: Vec<Value>) -> Result<BindingFuture> {
+                decode_args!(name, params, $($arg),*);
+                let future = self($($arg),*);
+                Ok(Box::pin(async move { Ok(serde_json::to_value(future.await?)?) }))
+            }
+
+            fn signature(name: &str, generator: &mut SchemaGenerator) -> Signature {
+                Signature {
+                    name: name.to_string(),
+                    params: Some(vec![$(Type::of::<$arg>(generator)),*]),
+                    returns: Some(Type::of::<R>(generator)),
+                    stream: false,
+                }
+            }
+        }
+
+        impl<F, Fut, T, $($arg,)*> StreamBinding<($($arg,)*), T> for F
+        where
+            F: FnMut($($arg,)* Sink<T>) -> Fut + 'static,
+            Fut: Future<Output = Result<()>> + Send + 'static,
+            T: Serialize + JsonSchema,
+            $($arg: DeserializeOwned + JsonSchema,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call(
+                &mut self,
+                name: &str,
+                params: Vec<Value>,
+                sender: StreamSender,
+            ) -> Result<BindingFuture> {
+                decode_args!(name, params, $($arg),*);
+                let sink = Sink {
+                    sender,
+                    item: PhantomData,
+                };
+                let future = self($($arg,)* sink);
+                Ok(Box::pin(async move { future.await.map(|()| Value::Null) }))
+            }
+
+            fn signature(name: &str, generator: &mut SchemaGenerator) -> Signature {
+                Signature {
+                    name: name.to_string(),
+                    params: Some(vec![$(Type::of::<$arg>(generator)),*]),
+                    returns: Some(Type::of::<T>(generator)),
+                    stream: true,
+                }
+            }
+        }
+    };
+}
+
+impl_binding!();
+impl_binding!(A1);
+impl_binding!(A1, A2);
+impl_binding!(A1, A2, A3);
+impl_binding!(A1, A2, A3, A4);
+impl_binding!(A1, A2, A3, A4, A5);
+impl_binding!(A1, A2, A3, A4, A5, A6);
+
+/// The type of an argument or return value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Type {
+    /// The name of the Rust type, without module paths.
+    pub name: String,
+    /// The schema of the JSON the type is encoded as.
+    pub schema: Schema,
+}
+
+impl Type {
+    pub fn of<T: JsonSchema>(generator: &mut SchemaGenerator) -> Type {
+        Type {
+            name: short_type_name(std::any::type_name::<T>()),
+            schema: generator.subschema_for::<T>(),
+        }
+    }
+}
+
+/// Removes the module paths from a type name, e.g. `alloc::vec::Vec<core::option::Option<f64>>`
+/// becomes `Vec<Option<f64>>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut path = String::new();
+    for c in name.chars().chain(std::iter::once('\0')) {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+            continue;
+        }
+
+        short.push_str(path.rsplit("::").next().unwrap_or_default());
+        path.clear();
+        if c != '\0' {
+            short.push(c);
+        }
+    }
+    short
+}
+
+/// The signature of a binding. Bindings that take raw JSON arguments have no known argument or
+/// return types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub name: String,
+    pub params: Option<Vec<Type>>,
+    pub returns: Option<Type>,
+    /// Whether the binding streams items of its return type, instead of returning once.
+    pub stream: bool,
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let params = match &self.params {
+            Some(params) => params
+                .iter()
+                .map(|param| param.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            None => "..".to_string(),
+        };
+        let returns = self.returns.as_ref().map_or("Value", |ty| ty.name.as_str());
+
+        match self.stream {
+            true => write!(f, "{}({params}) -> Stream<{returns}>", self.name),
+            false => write!(f, "{}({params}) -> {returns}", self.name),
+        }
+    }
+}
+
+/// The bindings registered on a window, and their signatures.
+#[derive(Debug, Default)]
+pub struct Registry {
+    generator: SchemaGenerator,
+    signatures: BTreeMap<String, Signature>,
+}
+
+impl Registry {
+    /// Records a typed binding, replacing any binding with the same name.
+    pub fn register<F: Binding<Args>, Args>(&mut self, name: &str) -> &Signature {
+        let signature = F::signature(name, &mut self.generator);
+        self.insert(signature)
+    }
+
+    /// Records an asynchronous binding, replacing any binding with the same name.
+    pub fn register_async<F: AsyncBinding<Args>, Args>(&mut self, name: &str) -> &Signature {
+        let signature = F::signature(name, &mut self.generator);
+        self.insert(signature)
+    }
+
+    /// Records a streaming binding, replacing any binding with the same name.
+    pub fn register_stream<F: StreamBinding<Args, T>, Args, T>(
+        &mut self,
+        name: &str,
+    ) -> &Signature {
+        let signature = F::signature(name, &mut self.generator);
+        self.insert(signature)
+    }
+
+    /// Records a binding taking raw JSON arguments, replacing any binding with the same name.
+    pub fn register_untyped(&mut self, name: &str) -> &Signature {
+        self.insert(Signature {
+            name: name.to_string(),
+            params: None,
+            returns: None,
+            stream: false,
+        })
+    }
+
+    fn insert(&mut self, signature: Signature) -> &Signature {
+        let name = signature.name.clone();
+        self.signatures.insert(name.clone(), signature);
+        &self.signatures[&name]
+    }
+
+    /// Returns the signatures of all bindings, ordered by name.
+    pub fn signatures(&self) -> impl Iterator<Item = &Signature> {
+        self.signatures.values()
+    }
+
+    /// Returns the schemas of the named types the signatures refer to, by name.
+    pub fn definitions(&self) -> &Map<String, Schema> {
+        self.generator.definitions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        rc::Rc,
+        sync::{Arc, Mutex},
+    };
+
+    use anyhow::Result;
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+    use tokio::sync::Semaphore;
+
+    use super::{
+        add_credit, short_type_name, AsyncBinding, Binding, BindingFuture, Registry, RpcError,
+        Sink, StreamBinding, StreamSender, STREAM_CAPACITY,
+    };
+
+    #[derive(Debug, Serialize, Deserialize, JsonSchema)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    fn call<F: Binding<Args>, Args>(mut f: F, params: Value) -> Result<Value> {
+        let Value::Array(params) = params else {
+            panic!("params must be an array");
+        };
+        f.call("test", params)
+    }
+
+    #[test]
+    fn test_call_typed_binding() {
+        let add = |a: f64, b: f64| Ok(a + b);
+        assert_eq!(call(add, json!([1, 2.5])).unwrap(), json!(3.5));
+
+        let point = |p: Point, dx: Option<i32>| {
+            Ok(Point {
+                x: p.x + dx.unwrap_or_default(),
+                ..p
+            })
+        };
+        assert_eq!(
+            call(point, json!([{ "x": 1, "y": 2 }, 3])).unwrap(),
+            json!({ "x": 4, "y": 2 })
+        );
+        // optional arguments can be left out
+        assert_eq!(
+            call(point, json!([{ "x": 1, "y": 2 }])).unwrap(),
+            json!({ "x": 1, "y": 2 })
+        );
+
+        let nothing = || Ok(());
+        assert_eq!(call(nothing, json!([])).unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_call_async_binding() {
+        fn call_async<F: AsyncBinding<Args>, Args>(mut f: F, params: Value) -> Result<Value> {
+            let Value::Array(params) = params else {
+                panic!("params must be an array");
+            };
+            let future = f.call("test", params)?;
+            tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(future)
+        }
+
+        let add = |a: f64, b: f64| async move { Ok(a + b) };
+        assert_eq!(call_async(add, json!([1, 2.5])).unwrap(), json!(3.5));
+
+        // arguments are decoded before the future is created
+        let called = Rc::new(Cell::new(false));
+        let mut never = {
+            let called = called.clone();
+            move |_a: f64| {
+                called.set(true);
+                async { Ok(()) }
+            }
+        };
+        assert_eq!(
+            AsyncBinding::call(&mut never, "test", vec![json!("1")])
+                .err()
+                .unwrap()
+                .to_string(),
+            "invalid argument 1 of `test`: invalid type: string \"1\", expected f64"
+        );
+        assert!(!called.get());
+
+        let fail = |reason: String| async move { Err::<(), _>(anyhow::anyhow!(reason)) };
+        assert_eq!(
+            call_async(fail, json!(["nope"])).unwrap_err().to_string(),
+            "nope"
+        );
+    }
+
+    #[test]
+    fn test_call_stream_binding() {
+        fn call_stream<F: StreamBinding<Args, T>, Args, T>(
+            mut f: F,
+            params: Value,
+            credit: Arc<Semaphore>,
+            delivered: Arc<Mutex<Vec<Value>>>,
+        ) -> BindingFuture {
+            let Value::Array(params) = params else {
+                panic!("params must be an array");
+            };
+            let sender = StreamSender::new(credit, move |item| {
+                delivered.lock().unwrap().push(item);
+                Ok(())
+            });
+            f.call("test", params, sender).unwrap()
+        }
+
+        let count = |n: u32, sink: Sink<u32>| async move {
+            for i in 0..n {
+                sink.send(i).await?;
+            }
+            Ok(())
+        };
+        let credit = Arc::new(Semaphore::new(2));
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let future = call_stream(count, json!([5]), credit.clone(), delivered.clone());
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let task = tokio::spawn(future);
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+            // the stream waits for the page once it has used up its credit
+            assert_eq!(*delivered.lock().unwrap(), [json!(0), json!(1)]);
+            assert!(!task.is_finished());
+
+            credit.add_permits(3);
+            assert_eq!(task.await.unwrap().unwrap(), Value::Null);
+            assert_eq!(delivered.lock().unwrap().len(), 5);
+
+            // once the page stops reading, sending fails
+            let credit = Arc::new(Semaphore::new(0));
+            let future = call_stream(count, json!([1]), credit.clone(), delivered.clone());
+            credit.close();
+            assert_eq!(
+                future.await.unwrap_err().to_string(),
+                "the stream was closed"
+            );
+        });
+    }
+
+    #[test]
+    fn test_add_credit() {
+        let credit = Semaphore::new(STREAM_CAPACITY);
+        credit.try_acquire_many(3).unwrap().forget();
+
+        add_credit(&credit, 2);
+        assert_eq!(credit.available_permits(), STREAM_CAPACITY - 1);
+
+        // credit beyond what the stream handed out is ignored
+        add_credit(&credit, usize::MAX);
+        assert_eq!(credit.available_permits(), STREAM_CAPACITY);
+        add_credit(&credit, 1);
+        assert_eq!(credit.available_permits(), STREAM_CAPACITY);
+    }
+
+    #[test]
+    fn test_argument_errors() {
+        let add = |a: f64, b: f64| Ok(a + b);
+        let error = |params| call(add, params).unwrap_err().to_string();
+
+        assert_eq!(
+            error(json!([1, "2"])),
+            "invalid argument 2 of `test`: invalid type: string \"2\", expected f64"
+        );
+        assert_eq!(
+            error(json!([1])),
+            "`test` takes 2 arguments, but argument 2 is missing"
+        );
+        assert_eq!(
+            error(json!([1, 2, 3])),
+            "`test` takes 2 arguments, but 3 were given"
+        );
+
+        let point = |p: Point| Ok(p);
+        assert_eq!(
+            call(point, json!([{ "x": 1 }])).unwrap_err().to_string(),
+            "invalid argument 1 of `test`: missing field `y`"
+        );
+
+        // errors returned by the binding itself are passed through
+        let fail = |reason: String| -> Result<()> { anyhow::bail!(reason) };
+        assert_eq!(call(fail, json!(["nope"])).unwrap_err().to_string(), "nope");
+    }
+
+    #[test]
+    fn test_rpc_errors() {
+        let add = |a: f64, b: f64| Ok(a + b);
+        let error = |params| RpcError::from(call(add, params).unwrap_err());
+
+        let invalid = error(json!([1, "2"]));
+        assert_eq!(invalid.code, RpcError::INVALID_PARAMS);
+        assert_eq!(invalid.data, Some(json!({ "argument": 2 })));
+        assert_eq!(error(json!([1, 2, 3])).code, RpcError::INVALID_PARAMS);
+
+        // errors returned by the binding keep their causes, unless they are already an `RpcError`
+        let fail = || -> Result<()> {
+            Err(anyhow::anyhow!("file not found").context("could not read config"))
+        };
+        assert_eq!(
+            serde_json::to_value(RpcError::from(call(fail, json!([])).unwrap_err())).unwrap(),
+            json!({
+                "code": RpcError::BINDING_ERROR,
+                "message": "could not read config: file not found",
+                "data": null,
+            })
+        );
+
+        let custom = || -> Result<()> { Err(RpcError::new(1, "busy", Some(json!(5))).into()) };
+        assert_eq!(
+            RpcError::from(call(custom, json!([])).unwrap_err()),
+            RpcError::new(1, "busy", Some(json!(5)))
+        );
+    }
+
+    #[test]
+    fn test_registry() {
+        fn register<F: Binding<Args>, Args>(registry: &mut Registry, name: &str, _f: F) {
+            registry.register::<F, Args>(name);
+        }
+        fn register_stream<F: StreamBinding<Args, T>, Args, T>(
+            registry: &mut Registry,
+            name: &str,
+            _f: F,
+        ) {
+            registry.register_stream::<F, Args, T>(name);
+        }
+
+        let mut registry = Registry::default();
+        register(&mut registry, "move", |_p: Point, _dx: Option<i32>| {
+            Ok(vec![1.0])
+        });
+        register(&mut registry, "close", || Ok(()));
+        registry.register_untyped("raw");
+        register_stream(
+            &mut registry,
+            "tail",
+            |_path: String, _sink: Sink<String>| async { Ok(()) },
+        );
+
+        let signatures: Vec<String> = registry.signatures().map(|s| s.to_string()).collect();
+        assert_eq!(
+            signatures,
+            [
+                "close() -> ()",
+                "move(Point, Option<i32>) -> Vec<f64>",
+                "raw(..) -> Value",
+                "tail(String) -> Stream<String>",
+            ]
+        );
+
+        // named types refer to their definitions
+        let point = &registry
+            .signatures()
+            .nth(1)
+            .unwrap()
+            .params
+            .as_ref()
+            .unwrap()[0];
+        assert_eq!(
+            serde_json::to_value(&point.schema).unwrap(),
+            json!({ "$ref": "#/definitions/Point" })
+        );
+    }
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(
+            short_type_name("alloc::vec::Vec<core::option::Option<f64>>"),
+            "Vec<Option<f64>>"
+        );
+        assert_eq!(
+            short_type_name("(flora::layout::Rect, [f64; 4], &str)"),
+            "(Rect, [f64; 4], &str)"
+        );
+        assert_eq!(short_type_name("()"), "()");
+    }
+}
@@ -2,6 +2,8 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
+use crate::{click_through::ClickThrough, layer::Layer};
+
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 #[command(propagate_version = true)]
@@ -37,4 +39,97 @@ pub enum FloraSubcommand {
         /// The specific widget to hide
         name: Option<String>,
     },
+    /// Show specific or all widgets if they are hidden, and hide them otherwise
+    Toggle {
+        /// Toggle all widgets
+        #[arg(long, action)]
+        all: bool,
+        /// The specific widget to toggle
+        name: Option<String>,
+    },
+    /// Load the content of specific or all widgets again
+    Reload {
+        /// Reload all widgets
+        #[arg(long, action)]
+        all: bool,
+        /// The specific widget to reload
+        name: Option<String>,
+    },
+    /// Move specific or all widgets into a z-order layer
+    Layer {
+        /// The layer to move the widgets into
+        #[arg(value_enum)]
+        layer: Layer,
+        /// Move all widgets
+        #[arg(long, action)]
+        all: bool,
+        /// The specific widget to move
+        name: Option<String>,
+    },
+    /// Change whether mouse input passes through specific or all widgets
+    ClickThrough {
+        /// The click-through mode
+        #[arg(value_enum)]
+        mode: ClickThrough,
+        /// Update all widgets
+        #[arg(long, action)]
+        all: bool,
+        /// The specific widget to update
+        name: Option<String>,
+    },
+    /// Toggle edit mode, where the widget can be moved and resized with the mouse. The new bounds
+    /// are written back into the widget's config when edit mode ends
+    Edit {
+        /// The widget to edit
+        name: String,
+    },
+    /// Show the settings page of a widget, generated from its `param-schema`. Saving it writes the
+    /// params back into the widget's config
+    Settings {
+        /// The widget to show the settings of
+        name: String,
+    },
+    /// Inspect widget configs
+    Config {
+        #[command(subcommand)]
+        command: ConfigSubcommand,
+    },
+    /// Inspect or clear what a widget keeps in `flora.storage`
+    Storage {
+        /// The name of the widget
+        name: String,
+        #[command(subcommand)]
+        command: StorageSubcommand,
+    },
+    /// Print TypeScript definitions for the `flora` API, e.g. `flora types > flora.d.ts`
+    Types,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum StorageSubcommand {
+    /// Print all stored entries as JSON
+    Dump,
+    /// Delete all stored entries
+    Clear,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigSubcommand {
+    /// Print a config
+    Show {
+        /// Print the config after applying its `extends` chain and the user-wide defaults
+        #[arg(long, action)]
+        resolved: bool,
+        /// The path to the config
+        path: PathBuf,
+    },
+    /// Convert a config between the .flora, .toml and .json formats
+    Convert {
+        /// The config to convert
+        input: PathBuf,
+        /// Where to write the converted config. The format is determined by its extension
+        output: PathBuf,
+    },
+    /// Print the JSON Schema describing the config options
+    Schema,
 }
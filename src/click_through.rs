@@ -0,0 +1,209 @@
+//! Lets mouse input pass through the widget window to the windows behind it.
+//!
+//! In [`ClickThrough::Interactive`] mode, the page declares the regions that should still receive
+//! input by marking elements with the `data-flora-interactive` attribute. The bounds of these
+//! elements are reported by [`INTERACTIVE_ZONES_SCRIPT`], and the window only accepts input while
+//! the cursor is over one of them.
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::layout::Rect;
+
+/// The name of the binding the page reports its interactive zones through.
+pub const INTERACTIVE_ZONES_BINDING: &str = "__floraSetInteractiveZones";
+
+/// Reports the bounds of all `data-flora-interactive` elements whenever the page changes, in
+/// CSS pixels along with the device pixel ratio.
+pub const INTERACTIVE_ZONES_SCRIPT: &str = r#"
+(function() {
+    var scheduled = false;
+    function report() {
+        scheduled = false;
+        var elements = document.querySelectorAll("[data-flora-interactive]");
+        var zones = Array.prototype.map.call(elements, function(element) {
+            var rect = element.getBoundingClientRect();
+            return [rect.left, rect.top, rect.width, rect.height];
+        });
+        window.__floraSetInteractiveZones(zones, window.devicePixelRatio);
+    }
+    function schedule() {
+        if (!scheduled) {
+            scheduled = true;
+            window.requestAnimationFrame(report);
+        }
+    }
+    document.addEventListener("DOMContentLoaded", function() {
+        new MutationObserver(schedule).observe(document.documentElement, {
+            subtree: true,
+            childList: true,
+            attributes: true,
+        });
+        window.addEventListener("resize", schedule);
+        window.addEventListener("scroll", schedule, true);
+        schedule();
+    });
+})();
+"#;
+
+/// How the widget window handles mouse input.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClickThrough {
+    /// The window receives all mouse input.
+    #[default]
+    Off,
+    /// All mouse input passes through the window.
+    Full,
+    /// Only elements with the `data-flora-interactive` attribute receive mouse input; everywhere
+    /// else, input passes through the window.
+    Interactive,
+}
+
+/// Deserializes the `click-through` option, where the bare `(click-through)` flag enables
+/// [`ClickThrough::Full`].
+pub fn deserialize_option<'de, D>(deserializer: D) -> Result<Option<ClickThrough>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct OptionVisitor;
+
+    impl<'de> de::Visitor<'de> for OptionVisitor {
+        type Value = Option<ClickThrough>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a click-through mode")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Some(ClickThrough::Full))
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Some(ClickThrough::Full))
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            ClickThrough::deserialize(deserializer).map(Some)
+        }
+    }
+
+    deserializer.deserialize_option(OptionVisitor)
+}
+
+/// Converts zones reported by the page, as `[x, y, width, height]` in CSS pixels, into client
+/// coordinates. Zones are rounded outwards, so partially covered pixels remain interactive.
+pub fn to_client_zones(zones: &[[f64; 4]], device_pixel_ratio: f64) -> Vec<Rect> {
+    zones
+        .iter()
+        .filter(|[_, _, width, height]| *width > 0.0 && *height > 0.0)
+        .map(|[x, y, width, height]| {
+            let left = (x * device_pixel_ratio).floor() as i32;
+            let top = (y * device_pixel_ratio).floor() as i32;
+            let right = ((x + width) * device_pixel_ratio).ceil() as i32;
+            let bottom = ((y + height) * device_pixel_ratio).ceil() as i32;
+
+            Rect {
+                x: left,
+                y: top,
+                width: right - left,
+                height: bottom - top,
+            }
+        })
+        .collect()
+}
+
+/// Whether the point, in client coordinates, is inside any of the interactive zones.
+pub fn hit_test(zones: &[Rect], x: i32, y: i32) -> bool {
+    zones.iter().any(|zone| zone.contains(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::layout::Rect;
+
+    use super::{hit_test, to_client_zones, ClickThrough};
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Options {
+        #[serde(default, deserialize_with = "super::deserialize_option")]
+        click_through: Option<ClickThrough>,
+    }
+
+    fn parse(s: &str) -> Option<ClickThrough> {
+        serde_lexpr::from_str::<Options>(s).unwrap().click_through
+    }
+
+    #[test]
+    fn test_parse_option() {
+        assert_eq!(parse("()"), None);
+        assert_eq!(parse("((click-through))"), Some(ClickThrough::Full));
+        assert_eq!(
+            parse("((click-through interactive))"),
+            Some(ClickThrough::Interactive)
+        );
+        assert_eq!(parse("((click-through off))"), Some(ClickThrough::Off));
+
+        assert!(serde_lexpr::from_str::<Options>("((click-through sometimes))").is_err());
+    }
+
+    #[test]
+    fn test_to_client_zones() {
+        let zones = to_client_zones(&[[10.0, 20.0, 30.0, 40.0], [0.5, 0.0, 0.0, 10.0]], 1.0);
+        assert_eq!(
+            zones,
+            [Rect {
+                x: 10,
+                y: 20,
+                width: 30,
+                height: 40
+            }]
+        );
+
+        // 150% scale, where the zone does not land on whole pixels
+        let zones = to_client_zones(&[[10.5, 1.0, 20.0, 1.0]], 1.5);
+        assert_eq!(
+            zones,
+            [Rect {
+                x: 15,
+                y: 1,
+                width: 31,
+                height: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_hit_test() {
+        let zones = [
+            Rect {
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+            Rect {
+                x: 50,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+        ];
+
+        assert!(hit_test(&zones, 0, 0));
+        assert!(hit_test(&zones, 9, 9));
+        assert!(hit_test(&zones, 55, 5));
+        assert!(!hit_test(&zones, 10, 5));
+        assert!(!hit_test(&zones, 30, 5));
+        assert!(!hit_test(&zones, -1, 5));
+        assert!(!hit_test(&[], 0, 0));
+    }
+}
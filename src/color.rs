@@ -1,19 +1,38 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
 pub const TRANSPARENT_COLOR: u32 = 0;
 
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Color {
-    Rgb { r: u32, g: u32, b: u32 },
-    Rgba { r: u32, g: u32, b: u32, alpha: u32 },
+    Rgb { r: u8, g: u8, b: u8 },
+    Rgba { r: u8, g: u8, b: u8, alpha: u8 },
     Transparent,
 }
 
 impl Color {
+    /// Creates a color from its components. Fully opaque colors are represented as [`Color::Rgb`].
+    fn from_components(r: u8, g: u8, b: u8, alpha: u8) -> Self {
+        match alpha {
+            0xFF => Self::Rgb { r, g, b },
+            _ => Self::Rgba { r, g, b, alpha },
+        }
+    }
+
     /// Returns the BGR value, where blue is encoded in the most significant bits, followed by
     /// green, and red.
     pub fn bgr(&self) -> u32 {
-        match self {
-            Self::Rgb { r, g, b } => b << 16 | g << 8 | r,
-            Self::Rgba { r, g, b, alpha: _ } => b << 16 | g << 8 | r,
+        match *self {
+            Self::Rgb { r, g, b } | Self::Rgba { r, g, b, alpha: _ } => {
+                u32::from_be_bytes([0, b, g, r])
+            }
             Self::Transparent => TRANSPARENT_COLOR,
         }
     }
@@ -21,11 +40,557 @@ impl Color {
     /// Returns the ARGB value, where alpha is encoded in the most significant bits, followed by
     /// red, green, and blue.
     pub fn argb(&self) -> u32 {
-        match self {
-            // 0xFF alpha = opaque
-            Self::Rgb { r, g, b } => 0xFF << 24 | r << 16 | g << 8 | b,
-            Self::Rgba { r, g, b, alpha } => alpha << 24 | r << 16 | g << 8 | b,
+        match *self {
+            Self::Rgb { r, g, b } | Self::Rgba { r, g, b, alpha: _ } => {
+                u32::from_be_bytes([self.alpha(), r, g, b])
+            }
             Self::Transparent => TRANSPARENT_COLOR,
         }
     }
+
+    /// Returns the alpha value, where 0 is fully transparent and 0xFF is opaque.
+    pub fn alpha(&self) -> u8 {
+        match *self {
+            // 0xFF alpha = opaque
+            Self::Rgb { .. } => 0xFF,
+            Self::Rgba { alpha, .. } => alpha,
+            Self::Transparent => 0,
+        }
+    }
+}
+
+/// Converts an opacity from 0.0 (invisible) to 1.0 (opaque) into an alpha value. Out of range
+/// opacities are clamped.
+pub fn opacity_to_alpha(opacity: f64) -> u8 {
+    (opacity.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Formats the color in its canonical form: `#rrggbb` for opaque colors, `#rrggbbaa` for
+/// translucent colors, and `transparent`.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rgb { r, g, b } => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            Self::Rgba { r, g, b, alpha } => write!(f, "#{r:02x}{g:02x}{b:02x}{alpha:02x}"),
+            Self::Transparent => write!(f, "transparent"),
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    /// Parses a CSS color: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`, `hsl()`,
+    /// `hsla()`, a named color, or `transparent`. Both the comma separated and the space separated
+    /// (`rgb(30 30 46 / 50%)`) function syntaxes are supported.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let color = s.trim().to_ascii_lowercase();
+
+        if color == "transparent" {
+            return Ok(Self::Transparent);
+        }
+        if let Some(hex) = color.strip_prefix('#') {
+            return parse_hex(hex).map_err(|e| anyhow!("invalid color `{s}`: {e}"));
+        }
+        if let Some((function, args)) = color
+            .strip_suffix(')')
+            .and_then(|color| color.split_once('('))
+        {
+            let parsed = match function.trim() {
+                "rgb" | "rgba" => parse_rgb(args),
+                "hsl" | "hsla" => parse_hsl(args),
+                function => Err(anyhow!("unknown color function `{function}`")),
+            };
+            return parsed.map_err(|e| anyhow!("invalid color `{s}`: {e}"));
+        }
+
+        NAMED_COLORS
+            .binary_search_by_key(&color.as_str(), |(name, _)| name)
+            .map(|i| {
+                let [_, r, g, b] = NAMED_COLORS[i].1.to_be_bytes();
+                Self::from_components(r, g, b, 0xFF)
+            })
+            .map_err(|_| {
+                anyhow!(
+                    "unknown color `{s}`, expected a hex color (e.g. `#1e1e2e`), `rgb()`, \
+                     `hsl()` or a CSS color name"
+                )
+            })
+    }
+}
+
+/// Parses the digits of a hex color, after the `#`.
+fn parse_hex(hex: &str) -> Result<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("`{hex}` is not a hexadecimal number");
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap();
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+
+    Ok(match hex.len() {
+        // each digit is repeated, e.g. `#f80` is `#ff8800`
+        3 => Color::from_components(digit(0) * 0x11, digit(1) * 0x11, digit(2) * 0x11, 0xFF),
+        4 => Color::from_components(
+            digit(0) * 0x11,
+            digit(1) * 0x11,
+            digit(2) * 0x11,
+            digit(3) * 0x11,
+        ),
+        6 => Color::from_components(pair(0), pair(2), pair(4), 0xFF),
+        8 => Color::from_components(pair(0), pair(2), pair(4), pair(6)),
+        len => bail!("expected 3, 4, 6 or 8 hex digits, found {len}"),
+    })
+}
+
+/// Parses the arguments of `rgb()` and `rgba()`.
+fn parse_rgb(args: &str) -> Result<Color> {
+    let ([r, g, b], alpha) = split_args(args)?;
+
+    Ok(Color::from_components(
+        parse_channel(r)?,
+        parse_channel(g)?,
+        parse_channel(b)?,
+        alpha.map_or(Ok(0xFF), parse_alpha)?,
+    ))
+}
+
+/// Parses the arguments of `hsl()` and `hsla()`.
+fn parse_hsl(args: &str) -> Result<Color> {
+    let ([h, s, l], alpha) = split_args(args)?;
+    let (r, g, b) = hsl_to_rgb(
+        parse_hue(h)?,
+        parse_percentage(s, "saturation")?,
+        parse_percentage(l, "lightness")?,
+    );
+
+    Ok(Color::from_components(
+        r,
+        g,
+        b,
+        alpha.map_or(Ok(0xFF), parse_alpha)?,
+    ))
+}
+
+/// Splits the arguments of a color function into its three components and an optional alpha.
+/// Arguments are either separated by commas, with the alpha as the fourth argument, or separated
+/// by spaces, with the alpha after a `/`.
+fn split_args(args: &str) -> Result<([&str; 3], Option<&str>)> {
+    let (args, alpha) = match args.split_once('/') {
+        Some((args, alpha)) => (args, Some(alpha.trim())),
+        None => (args, None),
+    };
+
+    let args: Vec<&str> = if args.contains(',') {
+        args.split(',').map(str::trim).collect()
+    } else {
+        args.split_whitespace().collect()
+    };
+
+    match (&args[..], alpha) {
+        (&[a, b, c], alpha) => Ok(([a, b, c], alpha)),
+        (&[a, b, c, alpha], None) => Ok(([a, b, c], Some(alpha))),
+        _ => bail!(
+            "expected 3 components and an optional alpha, found {} arguments",
+            args.len() + alpha.iter().count()
+        ),
+    }
+}
+
+/// Parses a finite number.
+fn parse_number(value: &str, what: &str) -> Result<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| anyhow!("{what} `{value}` is not a number"))
+}
+
+/// Parses a number within `0..=max`, or a percentage of `max` if it ends with `%`.
+fn parse_ranged(value: &str, max: f64, what: &str) -> Result<f64> {
+    let (number, max_number) = match value.strip_suffix('%') {
+        Some(percentage) => (parse_number(percentage, what)? / 100.0 * max, 100.0),
+        None => (parse_number(value, what)?, max),
+    };
+    if !(0.0..=max).contains(&number) {
+        bail!("{what} `{value}` must be between 0 and {max_number}");
+    }
+
+    Ok(number)
+}
+
+/// Parses a red, green or blue channel, from 0 to 255 or 0% to 100%.
+fn parse_channel(value: &str) -> Result<u8> {
+    Ok(parse_ranged(value, 255.0, "color component")?.round() as u8)
+}
+
+/// Parses an alpha value, from 0 to 1 or 0% to 100%, into an alpha from 0 to 255.
+fn parse_alpha(value: &str) -> Result<u8> {
+    Ok((parse_ranged(value, 1.0, "alpha")? * 255.0).round() as u8)
+}
+
+/// Parses a hue in degrees, optionally with a `deg` suffix. Hues outside of `0..360` wrap around.
+fn parse_hue(value: &str) -> Result<f64> {
+    let degrees = parse_number(value.strip_suffix("deg").unwrap_or(value), "hue")?;
+
+    Ok(degrees.rem_euclid(360.0))
+}
+
+/// Parses a saturation or lightness, from 0% to 100%, into a fraction from 0 to 1.
+fn parse_percentage(value: &str, what: &str) -> Result<f64> {
+    Ok(parse_ranged(value.strip_suffix('%').unwrap_or(value), 100.0, what)? / 100.0)
+}
+
+/// Converts a hue in degrees, and a saturation and lightness from 0 to 1 into RGB components.
+///
+/// See <https://www.w3.org/TR/css-color-4/#hsl-to-rgb>.
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+    let channel = |n: f64| {
+        let k = (n + hue / 30.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        let value = lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+
+        (value * 255.0).round() as u8
+    };
+
+    (channel(0.0), channel(8.0), channel(4.0))
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+impl JsonSchema for Color {
+    fn schema_name() -> String {
+        "Color".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// The CSS named colors, sorted by name so they can be binary searched.
+///
+/// See <https://www.w3.org/TR/css-color-4/#named-colors>.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{opacity_to_alpha, Color, NAMED_COLORS};
+
+    fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color::Rgb { r, g, b }
+    }
+
+    fn rgba(r: u8, g: u8, b: u8, alpha: u8) -> Color {
+        Color::Rgba { r, g, b, alpha }
+    }
+
+    fn parse(s: &str) -> Color {
+        s.parse()
+            .unwrap_or_else(|e| panic!("could not parse `{s}`: {e}"))
+    }
+
+    fn parse_err(s: &str) -> String {
+        match s.parse::<Color>() {
+            Ok(color) => panic!("`{s}` parsed as {color:?}"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse("#1e1e2e"), rgb(0x1e, 0x1e, 0x2e));
+        assert_eq!(parse("#1E1E2E80"), rgba(0x1e, 0x1e, 0x2e, 0x80));
+        assert_eq!(parse("#f80"), rgb(0xff, 0x88, 0x00));
+        assert_eq!(parse("#f808"), rgba(0xff, 0x88, 0x00, 0x88));
+        // a fully opaque alpha is normalized away
+        assert_eq!(parse("#1e1e2eff"), rgb(0x1e, 0x1e, 0x2e));
+        assert_eq!(parse("  #000  "), rgb(0, 0, 0));
+
+        assert!(parse_err("#1e1e2").contains("found 5"));
+        assert!(parse_err("#1e1e2g").contains("not a hexadecimal number"));
+        assert!(parse_err("#+1e1e2").contains("not a hexadecimal number"));
+        assert!(parse_err("#ééé").contains("not a hexadecimal number"));
+        assert!(parse_err("#").contains("found 0"));
+        assert!(parse_err("1e1e2e").contains("unknown color"));
+    }
+
+    #[test]
+    fn test_parse_rgb() {
+        assert_eq!(parse("rgb(30, 30, 46)"), rgb(30, 30, 46));
+        assert_eq!(parse("RGB(30,30,46)"), rgb(30, 30, 46));
+        assert_eq!(parse("rgba(30, 30, 46, 0.5)"), rgba(30, 30, 46, 128));
+        assert_eq!(parse("rgb(30, 30, 46, 50%)"), rgba(30, 30, 46, 128));
+        assert_eq!(parse("rgb(30 30 46)"), rgb(30, 30, 46));
+        assert_eq!(parse("rgb(30 30 46 / 0.25)"), rgba(30, 30, 46, 64));
+        assert_eq!(parse("rgba(100% 50% 0% / 100%)"), rgb(255, 128, 0));
+        assert_eq!(parse("rgb(0.4 254.6 0)"), rgb(0, 255, 0));
+        assert_eq!(parse("rgba(0, 0, 0, 0)"), rgba(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_parse_rgb_invalid() {
+        assert!(parse_err("rgb(256, 0, 0)").contains("must be between 0 and 255"));
+        assert!(parse_err("rgb(-1, 0, 0)").contains("must be between 0 and 255"));
+        assert!(parse_err("rgb(101%, 0, 0)").contains("must be between 0 and 100"));
+        assert!(parse_err("rgba(0, 0, 0, 1.5)").contains("alpha `1.5` must be between 0 and 1"));
+        assert!(parse_err("rgb(0, 0)").contains("found 2 arguments"));
+        assert!(parse_err("rgb(0, 0, 0, 0, 0)").contains("found 5 arguments"));
+        assert!(parse_err("rgb(0 0 0 0 / 1)").contains("found 5 arguments"));
+        assert!(parse_err("rgb(red, 0, 0)").contains("`red` is not a number"));
+        assert!(parse_err("rgb(NaN, 0, 0)").contains("is not a number"));
+        assert!(parse_err("rgb(inf, 0, 0)").contains("is not a number"));
+        assert!(parse_err("rgb(0, 0, 0").contains("unknown color"));
+        assert!(parse_err("cmyk(0, 0, 0, 0)").contains("unknown color function `cmyk`"));
+    }
+
+    #[test]
+    fn test_parse_hsl() {
+        assert_eq!(parse("hsl(0, 100%, 50%)"), rgb(255, 0, 0));
+        assert_eq!(parse("hsl(120, 100%, 25%)"), rgb(0, 128, 0));
+        assert_eq!(parse("hsl(240deg 100% 50%)"), rgb(0, 0, 255));
+        assert_eq!(parse("hsl(600 100% 50%)"), rgb(0, 0, 255));
+        assert_eq!(parse("hsl(-120 100% 50%)"), rgb(0, 0, 255));
+        assert_eq!(parse("hsl(0 0% 100%)"), rgb(255, 255, 255));
+        assert_eq!(parse("hsl(240, 21%, 15%)"), rgb(30, 30, 46));
+        assert_eq!(parse("hsla(270, 50%, 40%, 0.5)"), rgba(102, 51, 153, 128));
+        assert_eq!(parse("hsl(270 50% 40% / 50%)"), rgba(102, 51, 153, 128));
+
+        assert!(parse_err("hsl(0, 101%, 50%)").contains("saturation"));
+        assert!(parse_err("hsl(0, 100%, -5%)").contains("lightness"));
+        assert!(parse_err("hsl(red, 100%, 50%)").contains("hue `red` is not a number"));
+    }
+
+    #[test]
+    fn test_parse_named() {
+        assert_eq!(parse("rebeccapurple"), rgb(0x66, 0x33, 0x99));
+        assert_eq!(parse("White"), rgb(0xff, 0xff, 0xff));
+        assert_eq!(parse("transparent"), Color::Transparent);
+        assert_eq!(parse("TRANSPARENT"), Color::Transparent);
+
+        assert!(parse_err("notacolor").contains("unknown color `notacolor`"));
+    }
+
+    #[test]
+    fn test_named_colors_sorted() {
+        assert_eq!(NAMED_COLORS.len(), 148);
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        // every named color can be looked up
+        for (name, _) in NAMED_COLORS {
+            assert!(name.parse::<Color>().is_ok(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(rgb(0x1e, 0x1e, 0x2e).to_string(), "#1e1e2e");
+        assert_eq!(rgba(0x1e, 0x1e, 0x2e, 0x80).to_string(), "#1e1e2e80");
+        assert_eq!(Color::Transparent.to_string(), "transparent");
+
+        // displaying then parsing results in the same color
+        for s in [
+            "#F80",
+            "rgba(30 30 46 / 0.5)",
+            "hsl(120, 100%, 25%)",
+            "navy",
+        ] {
+            let color = parse(s);
+            assert_eq!(parse(&color.to_string()), color, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(rgb(0x1e, 0x1e, 0x2e).bgr(), 0x2e1e1e);
+        assert_eq!(rgb(0x1e, 0x1e, 0x2e).argb(), 0xff1e1e2e);
+        assert_eq!(rgba(0x1e, 0x1e, 0x2e, 0x80).argb(), 0x801e1e2e);
+        assert_eq!(Color::Transparent.argb(), 0);
+        assert_eq!(Color::Transparent.alpha(), 0);
+    }
+
+    #[test]
+    fn test_serde() {
+        let color: Color = serde_lexpr::from_str("\"#1e1e2e80\"").unwrap();
+        assert_eq!(color.alpha(), 0x80);
+        assert_eq!(serde_json::to_string(&color).unwrap(), "\"#1e1e2e80\"");
+
+        let color: Color = serde_json::from_str("\"rgb(30 30 46)\"").unwrap();
+        assert_eq!(serde_json::to_string(&color).unwrap(), "\"#1e1e2e\"");
+
+        let err = serde_json::from_str::<Color>("\"rgb(300 0 0)\"").unwrap_err();
+        assert!(err.to_string().contains("must be between 0 and 255"));
+    }
+
+    #[test]
+    fn test_opacity_to_alpha() {
+        assert_eq!(opacity_to_alpha(1.0), 255);
+        assert_eq!(opacity_to_alpha(0.85), 217);
+        assert_eq!(opacity_to_alpha(0.0), 0);
+        assert_eq!(opacity_to_alpha(1.5), 255);
+        assert_eq!(opacity_to_alpha(-1.0), 0);
+    }
 }
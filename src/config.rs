@@ -1,34 +1,575 @@
-use std::{fs, path::PathBuf, sync::atomic::Ordering};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+};
 
-use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_lexpr::Value;
 
-use crate::{CONTENT, CONTENT_URL, HEIGHT, NAME, POS_X, POS_Y, WIDTH};
+use crate::{
+    animation::{Hover, Transition},
+    click_through::{self, ClickThrough},
+    color::{self, Color},
+    hotkey::Hotkey,
+    layer::Layer,
+    layout::{self, Anchor, Length},
+    monitor::MonitorTarget,
+    params::{self, ParamSpec, Params},
+    BACKGROUND, CLICK_THROUGH, CONTENT, CONTENT_URL, HIDE_ON_FULLSCREEN, HOTKEYS, HOVER, LAYER,
+    LAYOUT, MONITOR, NAME, OPACITY, PARAMS, PARAM_SCHEMA, RPC_TIMEOUT, SNAP, TRANSITION,
+    TRANSITION_DURATION,
+};
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+/// The configuration of a flora widget.
+#[derive(Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
+    /// The path of a parent config to inherit options from. Relative paths are resolved against
+    /// the directory of the config that declares it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extends: Option<String>,
     /// A custom name for the widget. This is used to easily identify the widget for the end user.
+    #[serde(skip_serializing_if = "Option::is_none")]
     name: Option<String>,
-    /// The position of the widget
-    #[serde(rename = "pos")]
-    position: Option<(i32, i32)>,
-    /// The dimensions of the widget
-    #[serde(rename = "dim")]
-    dimension: Option<(i32, i32)>,
+    /// The monitor to place the widget on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    monitor: Option<MonitorTarget>,
+    /// The point of the monitor the widget is positioned relative to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    anchor: Option<Anchor>,
+    /// The position of the widget, as an offset from its anchor
+    #[serde(
+        rename = "pos",
+        default,
+        deserialize_with = "layout::deserialize_position",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(schema_with = "layout::position_schema")]
+    position: Option<(Length, Length)>,
+    /// The dimensions of the widget. `auto` sizes a dimension, or both, to the content
+    #[serde(
+        rename = "dim",
+        default,
+        deserialize_with = "layout::deserialize_dimension",
+        skip_serializing_if = "Option::is_none"
+    )]
+    #[schemars(schema_with = "layout::dimension_schema")]
+    dimension: Option<(Length, Length)>,
+    /// The smallest dimensions of the widget when it is sized to its content
+    #[serde(rename = "min-dim", skip_serializing_if = "Option::is_none")]
+    min_dimension: Option<(Length, Length)>,
+    /// The largest dimensions of the widget when it is sized to its content
+    #[serde(rename = "max-dim", skip_serializing_if = "Option::is_none")]
+    max_dimension: Option<(Length, Length)>,
+    /// The z-order layer of the widget
+    #[serde(skip_serializing_if = "Option::is_none")]
+    layer: Option<Layer>,
+    /// The background color of the widget, e.g. `#1e1e2e80`. Colors with an alpha below `ff` are
+    /// drawn with per-pixel transparency
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<Color>,
+    /// The opacity of the whole widget, from 0.0 (invisible) to 1.0 (opaque)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    opacity: Option<f64>,
+    /// Whether mouse input passes through the widget to the windows behind it. `(click-through)`
+    /// on its own lets all input pass through
+    #[serde(
+        default,
+        deserialize_with = "click_through::deserialize_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    click_through: Option<ClickThrough>,
+    /// The size of the grid, in pixels, the widget snaps to while it is moved or resized in edit
+    /// mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snap_grid: Option<u32>,
+    /// How close, in pixels, the widget must be to an edge of the monitor to snap to it while it is
+    /// moved or resized in edit mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snap_edges: Option<u32>,
+    /// How the widget appears and disappears when it is shown or hidden
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transition: Option<Transition>,
+    /// How long showing, hiding and hover transitions take, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transition_duration: Option<u32>,
+    /// Options that apply while the cursor is over the widget, e.g. `(on-hover (opacity 1.0))`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    on_hover: Option<Hover>,
+    /// Whether to hide the widget while an application is fullscreen on its monitor
+    #[serde(
+        default,
+        deserialize_with = "deserialize_flag",
+        skip_serializing_if = "Option::is_none"
+    )]
+    hide_on_fullscreen: Option<bool>,
+    /// Global hotkeys and the actions they trigger, e.g. `(hotkey "Ctrl+Alt+C" toggle)`. The
+    /// option can be given more than once
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hotkey: Option<Vec<Hotkey>>,
+    /// How long calls from the page to flora wait for a result before they are rejected, in
+    /// milliseconds. 0 waits forever
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rpc_timeout: Option<u32>,
+    /// Free-form values passed to the page as `flora.params`, e.g.
+    /// `(params (timezone "UTC") (threshold 80))`. They are updated live when the config changes
+    #[serde(
+        default,
+        deserialize_with = "params::deserialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    params: Option<Params>,
+    /// The params the widget takes, along with their types, defaults and ranges, e.g.
+    /// `(param-schema (threshold (type integer) (default 80) (min 0) (max 100)))`
+    #[serde(
+        default,
+        deserialize_with = "params::deserialize_schema",
+        skip_serializing_if = "Option::is_none"
+    )]
+    param_schema: Option<Vec<ParamSpec>>,
     /// The HTML or URL content of the widget
-    content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
     /// Whether to treat the content as a URL
+    #[serde(skip_serializing_if = "Option::is_none")]
     content_url: Option<bool>,
 }
 
+impl Config {
+    /// Merges this config on top of `base`. Any option set in this config takes precedence over
+    /// the same option in `base`.
+    ///
+    /// The `extends` option is never inherited, since it only applies to the file declaring it.
+    pub fn merge(self, base: Config) -> Config {
+        Config {
+            extends: self.extends,
+            name: self.name.or(base.name),
+            monitor: self.monitor.or(base.monitor),
+            anchor: self.anchor.or(base.anchor),
+            position: self.position.or(base.position),
+            dimension: self.dimension.or(base.dimension),
+            min_dimension: self.min_dimension.or(base.min_dimension),
+            max_dimension: self.max_dimension.or(base.max_dimension),
+            layer: self.layer.or(base.layer),
+            background: self.background.or(base.background),
+            opacity: self.opacity.or(base.opacity),
+            click_through: self.click_through.or(base.click_through),
+            snap_grid: self.snap_grid.or(base.snap_grid),
+            snap_edges: self.snap_edges.or(base.snap_edges),
+            transition: self.transition.or(base.transition),
+            transition_duration: self.transition_duration.or(base.transition_duration),
+            on_hover: self.on_hover.or(base.on_hover),
+            hide_on_fullscreen: self.hide_on_fullscreen.or(base.hide_on_fullscreen),
+            hotkey: self.hotkey.or(base.hotkey),
+            rpc_timeout: self.rpc_timeout.or(base.rpc_timeout),
+            params: self.params.or(base.params),
+            param_schema: self.param_schema.or(base.param_schema),
+            content: self.content.or(base.content),
+            content_url: self.content_url.or(base.content_url),
+        }
+    }
+
+    /// Returns the params of the widget, which are empty if none are given.
+    pub fn params(&self) -> Params {
+        self.params.clone().unwrap_or_default()
+    }
+
+    /// Returns the params the widget takes, which are empty if it doesn't declare any.
+    pub fn param_schema(&self) -> Vec<ParamSpec> {
+        self.param_schema.clone().unwrap_or_default()
+    }
+}
+
+/// The supported config file formats. The format of a config is determined by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// S-expressions, using the `.flora` extension.
+    Flora,
+    /// TOML, using the `.toml` extension.
+    Toml,
+    /// JSON, using the `.json` extension.
+    Json,
+}
+
+impl ConfigFormat {
+    pub const ALL: [ConfigFormat; 3] = [Self::Flora, Self::Toml, Self::Json];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Flora => "flora",
+            Self::Toml => "toml",
+            Self::Json => "json",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<ConfigFormat> {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+
+        match Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == ext)
+        {
+            Some(format) => Ok(format),
+            None => bail!("config extension must be one of .flora, .toml or .json"),
+        }
+    }
+}
+
+/// Deserializes an option that can be given as a flag, where the bare `(option)` means `true`.
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct FlagVisitor;
+
+    impl<'de> de::Visitor<'de> for FlagVisitor {
+        type Value = Option<bool>;
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a boolean")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Some(true))
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Some(true))
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            bool::deserialize(deserializer).map(Some)
+        }
+    }
+
+    deserializer.deserialize_option(FlagVisitor)
+}
+
+/// Generates the JSON Schema describing the config. This applies to configs in any format, though
+/// it is mostly useful for validating TOML and JSON configs.
+pub fn schema() -> RootSchema {
+    schemars::schema_for!(Config)
+}
+
+/// The name (without extension) of the user-wide defaults file, which is merged underneath every
+/// widget config.
+const DEFAULTS_FILE_STEM: &str = "defaults";
+
+#[cfg(test)]
 pub fn parse(config: &str) -> Result<Config> {
-    Ok(serde_lexpr::from_str(config)?)
+    parse_as(ConfigFormat::Flora, config)
+}
+
+pub fn parse_as(format: ConfigFormat, config: &str) -> Result<Config> {
+    Ok(match format {
+        ConfigFormat::Flora => serde_lexpr::from_value(&normalize(&config.parse()?)?)?,
+        ConfigFormat::Toml => toml::from_str(config)?,
+        ConfigFormat::Json => serde_json::from_str(config)?,
+    })
+}
+
+/// The options holding lengths, which may be `auto`.
+const LENGTH_OPTIONS: [&str; 3] = ["dim", "min-dim", "max-dim"];
+
+/// The options holding options of their own.
+const NESTED_OPTIONS: [&str; 1] = ["on-hover"];
+
+/// The options that can be given more than once, each time adding to a list.
+const REPEATED_OPTIONS: [&str; 1] = ["hotkey"];
+
+/// Adapts a config in the flora format to what serde-lexpr expects:
+///
+/// - Symbols can only be deserialized as enum variants, so the `auto` symbol in length options is
+///   replaced with the string `"auto"`, the way other lengths with units are written. Likewise, the
+///   `primary` symbol of `monitor` is replaced with `"primary"`, next to indices and device names.
+/// - Nested options are written like top-level options, as in `(on-hover (opacity 1.0))`, while
+///   serde-lexpr expects them in a single list, as in `(on-hover ((opacity 1.0)))`. Both forms are
+///   accepted.
+/// - Repeated options are written once per item, as in `(hotkey "Ctrl+Alt+C" toggle)`, while
+///   serde-lexpr expects a single option holding a list of items. All items are merged into the
+///   first occurrence of the option, along with any items already given as a list.
+/// - Params hold free-form values, which serde-lexpr can't deserialize without knowing their types.
+///   They are converted to JSON, and passed on as a JSON string. So is the param schema, since its
+///   defaults are free-form too.
+fn normalize(config: &Value) -> Result<Value> {
+    fn quote(value: &Value, name: &str) -> Value {
+        match value {
+            Value::Symbol(symbol) if &**symbol == name => Value::string(name),
+            Value::Cons(cons) => Value::cons(quote(cons.car(), name), quote(cons.cdr(), name)),
+            Value::Vector(values) => Value::vector(values.iter().map(|value| quote(value, name))),
+            _ => value.clone(),
+        }
+    }
+
+    /// Returns the items of a repeated option, or `None` if the value is in neither form.
+    fn repeated_items(value: &Value) -> Option<Vec<Value>> {
+        let first = value.as_cons()?.car();
+        if first.is_string() {
+            return Some(vec![value.clone()]);
+        }
+
+        let mut values = value.list_iter()?;
+        let items = match (values.next(), values.next()) {
+            (Some(items), None) => items,
+            _ => return None,
+        };
+        match items {
+            Value::Vector(items) => Some(items.to_vec()),
+            Value::Null => Some(Vec::new()),
+            _ if items.is_list() => Some(items.list_iter()?.cloned().collect()),
+            _ => None,
+        }
+    }
+
+    let Some(entries) = config.list_iter().filter(|_| config.is_list()) else {
+        return Ok(config.clone());
+    };
+
+    let mut repeated: Vec<(&str, Vec<Value>)> = Vec::new();
+    let mut merged = Vec::new();
+    for entry in entries {
+        let repeated_option = entry.as_pair().and_then(|(key, value)| {
+            let name = key
+                .as_symbol()
+                .filter(|name| REPEATED_OPTIONS.contains(name))?;
+            Some((name, repeated_items(value)?))
+        });
+        match repeated_option {
+            Some((name, items)) => match repeated.iter_mut().find(|(option, _)| *option == name) {
+                Some((_, existing)) => existing.extend(items),
+                None => {
+                    // a placeholder, replaced with all the items below
+                    merged.push(Value::symbol(name));
+                    repeated.push((name, items));
+                }
+            },
+            None => merged.push(entry.clone()),
+        }
+    }
+    for entry in merged.iter_mut() {
+        if let Some((name, items)) = repeated
+            .iter_mut()
+            .find(|(name, _)| entry.as_symbol() == Some(name))
+        {
+            *entry = Value::list([Value::symbol(*name), Value::list(std::mem::take(items))]);
+        }
+    }
+
+    merged
+        .iter()
+        .map(|entry| match entry.as_pair() {
+            Some((key, value)) => match key.as_symbol() {
+                Some("params") => {
+                    let params = params::from_lexpr(value).context("invalid params")?;
+                    let json = serde_json::to_string(&params)?;
+                    Ok(Value::cons(key.clone(), Value::string(json)))
+                }
+                Some("param-schema") => {
+                    let schema =
+                        params::schema_from_lexpr(value).context("invalid param schema")?;
+                    let json = serde_json::to_string(&schema)?;
+                    Ok(Value::cons(key.clone(), Value::string(json)))
+                }
+                Some(name) if LENGTH_OPTIONS.contains(&name) => {
+                    Ok(Value::cons(key.clone(), quote(value, "auto")))
+                }
+                Some("monitor") => Ok(Value::cons(key.clone(), quote(value, "primary"))),
+                Some(name)
+                    if NESTED_OPTIONS.contains(&name)
+                        && value.as_cons().is_some_and(|options| {
+                            options
+                                .car()
+                                .as_pair()
+                                .is_some_and(|(option, _)| option.is_symbol())
+                        }) =>
+                {
+                    Ok(Value::cons(
+                        key.clone(),
+                        Value::cons(value.clone(), Value::Null),
+                    ))
+                }
+                _ => Ok(entry.clone()),
+            },
+            None => Ok(entry.clone()),
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Value::list)
+}
+
+/// Replaces the params and the param schema serde-lexpr wrote with entries, the way they are
+/// read.
+fn write_params(config: &Value, params: Option<&Params>, schema: Option<&[ParamSpec]>) -> Value {
+    let entries = config.list_iter().into_iter().flatten();
+    Value::list(entries.map(|entry| match entry.as_pair() {
+        Some((key, _)) => match (key.as_symbol(), params, schema) {
+            (Some("params"), Some(params), _) => Value::cons(key.clone(), params::to_lexpr(params)),
+            (Some("param-schema"), _, Some(schema)) => {
+                Value::cons(key.clone(), params::schema_to_lexpr(schema))
+            }
+            _ => entry.clone(),
+        },
+        None => entry.clone(),
+    }))
+}
+
+/// Serializes the config into the given format.
+pub fn to_string_as(format: ConfigFormat, config: &Config) -> Result<String> {
+    Ok(match format {
+        ConfigFormat::Flora => {
+            let value = serde_lexpr::to_value(config)?;
+            write_params(
+                &value,
+                config.params.as_ref(),
+                config.param_schema.as_deref(),
+            )
+            .to_string()
+        }
+        ConfigFormat::Toml => toml::to_string_pretty(config)?,
+        ConfigFormat::Json => serde_json::to_string_pretty(config)?,
+    })
 }
 
-pub fn read(path: &PathBuf) -> Result<Config> {
-    serde_lexpr::from_slice(&fs::read(path).with_context(|| "could not read path")?)
-        .with_context(|| "could not parse config")
+pub fn read(path: &Path) -> Result<Config> {
+    let format = ConfigFormat::from_path(path)?;
+    let config = fs::read_to_string(path).with_context(|| "could not read path")?;
+    parse_as(format, &config).with_context(|| "could not parse config")
+}
+
+/// Writes the config to the given path, using the format matching its extension.
+pub fn write(path: &Path, config: &Config) -> Result<()> {
+    let format = ConfigFormat::from_path(path)?;
+    fs::write(path, to_string_as(format, config)?).with_context(|| "could not write path")
+}
+
+/// Returns the path of the user-wide defaults file.
+///
+/// On Windows this is `%APPDATA%/flora/defaults.flora`. Elsewhere, `$XDG_CONFIG_HOME` is used,
+/// falling back to `~/.config`. A `defaults.toml` or `defaults.json` file is used instead if one
+/// exists. The file does not need to exist.
+pub fn defaults_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("APPDATA")
+        .or_else(|| env::var_os("XDG_CONFIG_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?
+        .join("flora");
+
+    let defaults = ConfigFormat::ALL
+        .iter()
+        .map(|format| config_dir.join(format!("{DEFAULTS_FILE_STEM}.{}", format.extension())))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| config_dir.join(format!("{DEFAULTS_FILE_STEM}.flora")));
+
+    Some(defaults)
+}
+
+/// Reads the config at the given path and fully resolves it. This follows the `extends` chain of
+/// the config and merges the user-wide defaults file (if present) underneath the result.
+///
+/// If the config declares a param schema, its params are checked against it, and missing params
+/// get their defaults.
+pub fn resolve(path: &Path) -> Result<Config> {
+    let mut config = merge_defaults(path)?;
+    if let Some(schema) = &config.param_schema {
+        let params = params::validate(schema, &config.params()).context("invalid params")?;
+        config.params = Some(params);
+    }
+
+    Ok(config)
+}
+
+/// Returns the params the config at `path` sets, along with the configs it extends, but without
+/// the defaults of its param schema.
+pub fn configured_params(path: &Path) -> Result<Params> {
+    Ok(merge_defaults(path)?.params())
+}
+
+/// Follows the `extends` chain of the config at the given path, and merges the user-wide defaults
+/// file underneath the result.
+fn merge_defaults(path: &Path) -> Result<Config> {
+    let config = resolve_chain(path, &mut Vec::new())?;
+
+    let defaults = match defaults_path() {
+        Some(defaults) if defaults.is_file() => defaults,
+        _ => return Ok(config),
+    };
+
+    // the defaults file may itself be resolved directly (e.g. to inspect it), in which case there
+    // is nothing left to merge
+    if fs::canonicalize(&defaults)? == fs::canonicalize(path)? {
+        return Ok(config);
+    }
+
+    let defaults = resolve_chain(&defaults, &mut Vec::new())
+        .with_context(|| format!("could not resolve defaults file {}", defaults.display()))?;
+
+    Ok(config.merge(defaults))
+}
+
+/// Returns the files the config at the given path is resolved from: the configs of its `extends`
+/// chain, then the user-wide defaults file and the configs it extends. The defaults file is
+/// included even if it doesn't exist yet.
+pub fn source_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = chain_files(path);
+    if let Some(defaults) = defaults_path() {
+        for file in chain_files(&defaults) {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+/// Returns the configs of the `extends` chain of the config at the given path, as far as it can be
+/// resolved, starting with the config itself.
+fn chain_files(path: &Path) -> Vec<PathBuf> {
+    let mut visited = Vec::new();
+    let _ = resolve_chain(path, &mut visited);
+    if visited.is_empty() {
+        visited.push(path.to_path_buf());
+    }
+    visited
+}
+
+/// Resolves the `extends` chain of the config at the given path. `visited` holds the configs
+/// already seen in the chain, and is used to detect inheritance cycles.
+fn resolve_chain(path: &Path, visited: &mut Vec<PathBuf>) -> Result<Config> {
+    let path = fs::canonicalize(path)
+        .with_context(|| format!("could not find config {}", path.display()))?;
+
+    if visited.contains(&path) {
+        visited.push(path);
+        let chain = visited
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        bail!("config inheritance cycle detected: {chain}");
+    }
+    visited.push(path.clone());
+
+    let mut config =
+        read(&path).with_context(|| format!("could not load config {}", path.display()))?;
+
+    match config.extends.take() {
+        Some(parent) => {
+            let parent = match path.parent() {
+                Some(dir) => dir.join(parent),
+                None => PathBuf::from(parent),
+            };
+            Ok(config.merge(resolve_chain(&parent, visited)?))
+        }
+        None => Ok(config),
+    }
 }
 
 pub fn load_config(config: Config) {
@@ -36,18 +577,98 @@ pub fn load_config(config: Config) {
         let mut n = NAME.lock().unwrap();
         *n = name;
     }
-    if let Some(position) = config.position {
-        POS_X.store(position.0, Ordering::SeqCst);
-        POS_Y.store(position.1, Ordering::SeqCst);
+    if let Some(monitor) = config.monitor {
+        let mut m = MONITOR.lock().unwrap();
+        *m = monitor;
+    }
+    {
+        let mut layout = LAYOUT.lock().unwrap();
+        if let Some(anchor) = config.anchor {
+            layout.anchor = anchor;
+        }
+        if let Some(position) = config.position {
+            layout.position = position;
+        }
+        if let Some(dim) = config.dimension {
+            layout.dimension = dim;
+        }
+        if let Some(min) = config.min_dimension {
+            layout.min_dimension = Some(min);
+        }
+        if let Some(max) = config.max_dimension {
+            layout.max_dimension = Some(max);
+        }
+    }
+
+    if let Some(layer) = config.layer {
+        let mut l = LAYER.lock().unwrap();
+        *l = layer;
+    }
+
+    if let Some(background) = config.background {
+        let mut b = BACKGROUND.lock().unwrap();
+        *b = Some(background);
+    }
+
+    if let Some(opacity) = config.opacity {
+        OPACITY.store(color::opacity_to_alpha(opacity), Ordering::SeqCst);
+    }
+
+    if let Some(click_through) = config.click_through {
+        let mut c = CLICK_THROUGH.lock().unwrap();
+        *c = click_through;
+    }
+
+    if let Some(transition) = config.transition {
+        let mut t = TRANSITION.lock().unwrap();
+        *t = transition;
+    }
+
+    if let Some(duration) = config.transition_duration {
+        TRANSITION_DURATION.store(duration, Ordering::SeqCst);
+    }
+
+    if let Some(hide) = config.hide_on_fullscreen {
+        HIDE_ON_FULLSCREEN.store(hide, Ordering::SeqCst);
+    }
+
+    if let Some(hotkeys) = config.hotkey {
+        let mut h = HOTKEYS.lock().unwrap();
+        *h = hotkeys;
+    }
+
+    if let Some(timeout) = config.rpc_timeout {
+        RPC_TIMEOUT.store(timeout, Ordering::SeqCst);
+    }
+
+    if let Some(params) = config.params {
+        let mut p = PARAMS.lock().unwrap();
+        *p = params;
     }
-    if let Some(dim) = config.dimension {
-        WIDTH.store(dim.0, Ordering::SeqCst);
-        HEIGHT.store(dim.1, Ordering::SeqCst);
+
+    if let Some(schema) = config.param_schema {
+        let mut s = PARAM_SCHEMA.lock().unwrap();
+        *s = schema;
+    }
+
+    if let Some(hover) = config.on_hover {
+        let mut h = HOVER.lock().unwrap();
+        *h = hover;
     }
 
     {
+        let mut snap = SNAP.lock().unwrap();
+        if let Some(grid) = config.snap_grid {
+            snap.grid = Some(grid);
+        }
+        if let Some(edges) = config.snap_edges {
+            snap.edges = Some(edges);
+        }
+    }
+
+    if let Some(content) = config.content {
         let mut c = CONTENT.lock().unwrap();
-        *c = config.content;
+        *c = content;
     }
 
     if let Some(_) = config.content_url {
@@ -57,17 +678,68 @@ pub fn load_config(config: Config) {
 
 #[cfg(test)]
 mod tests {
-    use crate::config::Config;
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        animation::{Hover, Transition},
+        click_through::ClickThrough,
+        color::Color,
+        config::Config,
+        hotkey::{Hotkey, HotkeyAction},
+        layer::Layer,
+        layout::{Anchor, Length},
+        monitor::MonitorTarget,
+        params::Params,
+    };
+    use serde_json::json;
+    use tempfile::TempDir;
 
-    use super::parse;
+    use super::{chain_files, parse, parse_as, resolve_chain, schema, to_string_as, ConfigFormat};
+
+    /// Writes the given configs into a fresh temporary directory, which is removed once it is
+    /// dropped.
+    fn write_configs(configs: &[(&str, &str)]) -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, contents) in configs {
+            fs::write(dir.path().join(name), contents).unwrap();
+        }
+        dir
+    }
 
     #[test]
     fn test_print() {
         let config = Config {
+            extends: None,
             name: Some("flora".to_string()),
-            position: Some((100, 0)),
-            dimension: Some((200, 0)),
-            content: "".to_string(),
+            monitor: Some(MonitorTarget::Index(1)),
+            anchor: Some(Anchor::TopRight),
+            position: Some((Length::Pixels(100), Length::Pixels(0))),
+            dimension: Some((Length::Auto, Length::Pixels(0))),
+            min_dimension: Some((Length::Pixels(100), Length::Pixels(0))),
+            max_dimension: Some((Length::Percent(50.0), Length::Pixels(0))),
+            layer: Some(Layer::Desktop),
+            background: Some(Color::Rgba {
+                r: 0x1e,
+                g: 0x1e,
+                b: 0x2e,
+                alpha: 0x80,
+            }),
+            opacity: Some(0.85),
+            click_through: Some(ClickThrough::Interactive),
+            snap_grid: Some(10),
+            snap_edges: Some(8),
+            transition: Some(Transition::Slide),
+            transition_duration: Some(200),
+            on_hover: Some(Hover { opacity: Some(1.0) }),
+            hide_on_fullscreen: Some(true),
+            hotkey: Some(vec![Hotkey(
+                "Ctrl+Alt+C".parse().unwrap(),
+                HotkeyAction::Toggle,
+            )]),
+            rpc_timeout: Some(10_000),
+            params: None,
+            param_schema: None,
+            content: Some("".to_string()),
             content_url: Some(false),
         };
 
@@ -76,17 +748,443 @@ mod tests {
 
     #[test]
     fn test_parse() {
-        let config = parse("((pos #(0 0))\n(dim #(200 0)) (content . \"\"))").unwrap();
+        let config = parse("((pos #(0 0))\n(dim #(200 0)) (content \"\"))").unwrap();
 
         assert_eq!(
             config,
             Config {
+                extends: None,
                 name: None,
-                position: Some((0, 0)),
-                dimension: Some((200, 0)),
-                content: "".to_string(),
+                monitor: None,
+                anchor: None,
+                position: Some((Length::Pixels(0), Length::Pixels(0))),
+                dimension: Some((Length::Pixels(200), Length::Pixels(0))),
+                min_dimension: None,
+                max_dimension: None,
+                layer: None,
+                background: None,
+                opacity: None,
+                click_through: None,
+                snap_grid: None,
+                snap_edges: None,
+                transition: None,
+                transition_duration: None,
+                on_hover: None,
+                hide_on_fullscreen: None,
+                hotkey: None,
+                rpc_timeout: None,
+                params: None,
+                param_schema: None,
+                content: Some("".to_string()),
+                content_url: None,
+            }
+        )
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let expected = Config {
+            extends: None,
+            name: Some("flora".to_string()),
+            monitor: Some(MonitorTarget::Primary),
+            anchor: Some(Anchor::BottomRight),
+            position: Some((Length::Pixels(0), Length::Percent(10.0))),
+            dimension: Some((Length::Auto, Length::Auto)),
+            min_dimension: Some((Length::Pixels(100), Length::Dip(20.0))),
+            max_dimension: None,
+            layer: Some(Layer::Topmost),
+            background: Some(Color::Transparent),
+            opacity: Some(0.5),
+            click_through: Some(ClickThrough::Full),
+            snap_grid: None,
+            snap_edges: Some(8),
+            transition: Some(Transition::Fade),
+            transition_duration: None,
+            on_hover: Some(Hover { opacity: Some(1.0) }),
+            hide_on_fullscreen: Some(true),
+            hotkey: Some(vec![Hotkey(
+                "Win+Shift+R".parse().unwrap(),
+                HotkeyAction::Reload,
+            )]),
+            rpc_timeout: Some(5000),
+            params: Some(Params::from_iter([
+                ("timezone".to_string(), json!("UTC")),
+                ("threshold".to_string(), json!(80)),
+            ])),
+            param_schema: None,
+            content: Some("hi".to_string()),
+            content_url: Some(true),
+        };
+
+        let toml = r#"
+            name = "flora"
+            monitor = "primary"
+            anchor = "bottom-right"
+            pos = [0, "10%"]
+            dim = "auto"
+            min-dim = [100, "20dip"]
+            layer = "topmost"
+            background = "transparent"
+            opacity = 0.5
+            click-through = "full"
+            snap-edges = 8
+            transition = "fade"
+            hide-on-fullscreen = true
+            on-hover = { opacity = 1.0 }
+            hotkey = [["Win+Shift+R", "reload"]]
+            rpc-timeout = 5000
+            params = { timezone = "UTC", threshold = 80 }
+            content = "hi"
+            content-url = true
+        "#;
+        assert_eq!(parse_as(ConfigFormat::Toml, toml).unwrap(), expected);
+
+        let json = r#"{
+            "name": "flora",
+            "monitor": "primary",
+            "anchor": "bottom-right",
+            "pos": [0, "10%"],
+            "dim": "auto",
+            "min-dim": [100, "20dip"],
+            "layer": "topmost",
+            "background": "transparent",
+            "opacity": 0.5,
+            "click-through": "full",
+            "snap-edges": 8,
+            "transition": "fade",
+            "hide-on-fullscreen": true,
+            "on-hover": { "opacity": 1.0 },
+            "hotkey": [["Win+Shift+R", "reload"]],
+            "rpc-timeout": 5000,
+            "params": { "timezone": "UTC", "threshold": 80 },
+            "content": "hi",
+            "content-url": true
+        }"#;
+        assert_eq!(parse_as(ConfigFormat::Json, json).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_convert_round_trip() {
+        let config = parse(
+            r##"((extends "base.flora")
+                (name "flora")
+                (monitor 1)
+                (anchor top-right)
+                (pos #(-5 10))
+                (dim #(auto 20))
+                (max-dim #("50%" 20))
+                (layer desktop)
+                (background "#1e1e2e80")
+                (opacity 0.85)
+                (click-through interactive)
+                (snap-grid 10)
+                (snap-edges 8)
+                (transition slide)
+                (transition-duration 200)
+                (on-hover (opacity 0.5))
+                (hide-on-fullscreen)
+                (hotkey "Ctrl+Alt+C" toggle)
+                (hotkey "Win+Shift+E" edit)
+                (params
+                    (timezone "UTC")
+                    (threshold 80)
+                    (location (lat 59.9) (lon 10.7))
+                    (sensors #("cpu" "gpu")))
+                (param-schema
+                    (threshold (type integer) (default 80) (min 0) (max 100))
+                    (timezone (type string) (description "Where the clock is")))
+                (content "<div class=\"a\">hi</div>")
+                (content-url #f))"##,
+        )
+        .unwrap();
+
+        for from in ConfigFormat::ALL {
+            for to in ConfigFormat::ALL {
+                let converted = to_string_as(from, &config).unwrap();
+                let converted = parse_as(from, &converted).unwrap();
+                let converted = to_string_as(to, &converted).unwrap();
+
+                assert_eq!(
+                    parse_as(to, &converted).unwrap(),
+                    config,
+                    "{from:?} -> {to:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_monitor() {
+        let monitor = |s: &str| parse(s).unwrap().monitor;
+        assert_eq!(monitor("((monitor primary))"), Some(MonitorTarget::Primary));
+        assert_eq!(monitor("((monitor 1))"), Some(MonitorTarget::Index(1)));
+        assert_eq!(
+            monitor(r#"((monitor "\\\\.\\DISPLAY2"))"#),
+            Some(MonitorTarget::Name(r"\\.\DISPLAY2".to_string()))
+        );
+        assert!(parse("((monitor -1))").is_err());
+        assert!(parse("((monitor (index . 1)))").is_err());
+    }
+
+    #[test]
+    fn test_parse_auto_dimensions() {
+        let config = parse("((dim auto) (min-dim #(100 auto)))").unwrap();
+        assert_eq!(config.dimension, Some((Length::Auto, Length::Auto)));
+        assert_eq!(
+            config.min_dimension,
+            Some((Length::Pixels(100), Length::Auto))
+        );
+
+        let config = parse(r#"((dim #("50%" auto)) (content "auto"))"#).unwrap();
+        assert_eq!(
+            config.dimension,
+            Some((Length::Percent(50.0), Length::Auto))
+        );
+        assert_eq!(config.content, Some("auto".to_string()));
+
+        assert!(parse("((dim fill))").is_err());
+        assert!(parse("((pos auto))").is_err());
+        assert!(parse(r#"((pos #("auto" 0)))"#).is_err());
+        assert!(parse_as(ConfigFormat::Json, r#"{"pos": ["auto", 0]}"#).is_err());
+        assert!(parse_as(ConfigFormat::Toml, r#"pos = [0, "auto"]"#).is_err());
+        assert_eq!(
+            parse_as(ConfigFormat::Json, r#"{"pos": [0, "50%"]}"#)
+                .unwrap()
+                .position,
+            Some((Length::Pixels(0), Length::Percent(50.0)))
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_options() {
+        let expected = Some(Hover { opacity: Some(1.0) });
+        assert_eq!(
+            parse("((on-hover (opacity 1.0)))").unwrap().on_hover,
+            expected
+        );
+        assert_eq!(
+            parse("((on-hover ((opacity 1.0))))").unwrap().on_hover,
+            expected
+        );
+        assert_eq!(parse("((on-hover))").unwrap().on_hover, None);
+        assert!(parse("((on-hover 1.0))").is_err());
+    }
+
+    #[test]
+    fn test_parse_repeated_options() {
+        let hotkey = |chord: &str, action| Hotkey(chord.parse().unwrap(), action);
+        let expected = Some(vec![
+            hotkey("Ctrl+Alt+C", HotkeyAction::Toggle),
+            hotkey("Win+Shift+R", HotkeyAction::Reload),
+        ]);
+
+        let config = parse(
+            r#"((hotkey "Ctrl+Alt+C" toggle)
+                (name "flora")
+                (hotkey "Win+Shift+R" reload))"#,
+        )
+        .unwrap();
+        assert_eq!(config.hotkey, expected);
+        assert_eq!(config.name, Some("flora".to_string()));
+
+        // the form the option is printed in, and a mix of both forms
+        let printed = r#"((hotkey (#("Ctrl+Alt+C" toggle) #("Win+Shift+R" reload))))"#;
+        assert_eq!(parse(printed).unwrap().hotkey, expected);
+        let mixed = r#"((hotkey (("Ctrl+Alt+C" toggle))) (hotkey "Win+Shift+R" reload))"#;
+        assert_eq!(parse(mixed).unwrap().hotkey, expected);
+
+        let err = parse(r#"((hotkey "Ctrl+Alt" toggle))"#).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("invalid hotkey `Ctrl+Alt`"),
+            "{err:#}"
+        );
+        assert!(parse(r#"((hotkey "Ctrl+Alt+C" explode))"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_params() {
+        let config =
+            parse(r#"((params (timezone "UTC") (threshold 80) (compact) (location (lat 59.9))))"#)
+                .unwrap();
+        assert_eq!(
+            serde_json::Value::Object(config.params()),
+            json!({
+                "timezone": "UTC",
+                "threshold": 80,
+                "compact": true,
+                "location": { "lat": 59.9 },
+            })
+        );
+
+        assert_eq!(parse("((params))").unwrap().params, Some(Params::new()));
+        assert_eq!(parse("()").unwrap().params(), Params::new());
+
+        let err = parse("((params (threshold 80 90)))").unwrap_err();
+        assert!(format!("{err:#}").contains("invalid params"), "{err:#}");
+
+        let config = parse(
+            r#"((params (threshold 80))
+                (param-schema (threshold (type integer) (max 100)) (compact (type boolean))))"#,
+        )
+        .unwrap();
+        let schema = config.param_schema();
+        assert_eq!(
+            schema.iter().map(|spec| &spec.name).collect::<Vec<_>>(),
+            ["threshold", "compact"]
+        );
+        assert_eq!(schema[0].max, Some(100.0));
+        assert!(parse("((param-schema (threshold (type integer) (maximum 100))))").is_err());
+    }
+
+    #[test]
+    fn test_parse_flags() {
+        let hide_on_fullscreen = |s: &str| parse(s).unwrap().hide_on_fullscreen;
+
+        assert_eq!(hide_on_fullscreen("()"), None);
+        assert_eq!(hide_on_fullscreen("((hide-on-fullscreen))"), Some(true));
+        assert_eq!(hide_on_fullscreen("((hide-on-fullscreen #t))"), Some(true));
+        assert_eq!(hide_on_fullscreen("((hide-on-fullscreen #f))"), Some(false));
+        assert!(parse("((hide-on-fullscreen 1))").is_err());
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(&PathBuf::from("a/widget.toml")).unwrap(),
+            ConfigFormat::Toml
+        );
+        assert!(ConfigFormat::from_path(&PathBuf::from("widget.yaml")).is_err());
+        assert!(ConfigFormat::from_path(&PathBuf::from("widget")).is_err());
+    }
+
+    /// The committed schema must match the `Config` struct. If this fails, regenerate the schema
+    /// with `flora config schema > docs/flora.schema.json`.
+    #[test]
+    fn test_schema_in_sync() {
+        let committed: serde_json::Value =
+            serde_json::from_str(include_str!("../docs/flora.schema.json")).unwrap();
+
+        assert_eq!(serde_json::to_value(schema()).unwrap(), committed);
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = parse("((name \"base\") (pos #(10 10)) (dim #(200 20)))").unwrap();
+        let child = parse("((extends \"base.flora\") (pos #(0 0)) (content \"hi\"))").unwrap();
+
+        assert_eq!(
+            child.merge(base),
+            Config {
+                extends: Some("base.flora".to_string()),
+                name: Some("base".to_string()),
+                monitor: None,
+                anchor: None,
+                position: Some((Length::Pixels(0), Length::Pixels(0))),
+                dimension: Some((Length::Pixels(200), Length::Pixels(20))),
+                min_dimension: None,
+                max_dimension: None,
+                layer: None,
+                background: None,
+                opacity: None,
+                click_through: None,
+                snap_grid: None,
+                snap_edges: None,
+                transition: None,
+                transition_duration: None,
+                on_hover: None,
+                hide_on_fullscreen: None,
+                hotkey: None,
+                rpc_timeout: None,
+                params: None,
+                param_schema: None,
+                content: Some("hi".to_string()),
                 content_url: None,
             }
         )
     }
+
+    #[test]
+    fn test_resolve_extends_chain() {
+        let dir = write_configs(&[
+            ("root.flora", "((dim #(200 20)) (content \"root\"))"),
+            ("base.flora", "((extends \"root.flora\") (name \"base\"))"),
+            (
+                "widget.flora",
+                "((extends \"base.flora\") (content \"widget\"))",
+            ),
+        ]);
+
+        let config = resolve_chain(&dir.path().join("widget.flora"), &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            config,
+            Config {
+                extends: None,
+                name: Some("base".to_string()),
+                monitor: None,
+                anchor: None,
+                position: None,
+                dimension: Some((Length::Pixels(200), Length::Pixels(20))),
+                min_dimension: None,
+                max_dimension: None,
+                layer: None,
+                background: None,
+                opacity: None,
+                click_through: None,
+                snap_grid: None,
+                snap_edges: None,
+                transition: None,
+                transition_duration: None,
+                on_hover: None,
+                hide_on_fullscreen: None,
+                hotkey: None,
+                rpc_timeout: None,
+                params: None,
+                param_schema: None,
+                content: Some("widget".to_string()),
+                content_url: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_chain_files() {
+        let temp = write_configs(&[
+            ("root.flora", "((content \"root\"))"),
+            ("base.flora", "((extends \"root.flora\"))"),
+            ("widget.flora", "((extends \"base.flora\"))"),
+            ("broken.flora", "((extends \"missing.flora\"))"),
+        ]);
+        let dir = fs::canonicalize(temp.path()).unwrap();
+
+        assert_eq!(
+            chain_files(&dir.join("widget.flora")),
+            [
+                dir.join("widget.flora"),
+                dir.join("base.flora"),
+                dir.join("root.flora")
+            ]
+        );
+        // a broken chain is followed as far as it goes
+        assert_eq!(
+            chain_files(&dir.join("broken.flora")),
+            [dir.join("broken.flora")]
+        );
+        assert_eq!(
+            chain_files(&dir.join("missing.flora")),
+            [dir.join("missing.flora")]
+        );
+    }
+
+    #[test]
+    fn test_resolve_cycle() {
+        let dir = write_configs(&[
+            ("a.flora", "((extends \"b.flora\"))"),
+            ("b.flora", "((extends \"a.flora\"))"),
+        ]);
+
+        let err = resolve_chain(&dir.path().join("a.flora"), &mut Vec::new()).unwrap_err();
+
+        assert!(err.to_string().contains("cycle"));
+    }
 }
@@ -0,0 +1,544 @@
+//! Updates options in config files in place, keeping the formatting and comments of the rest of
+//! the file intact.
+//!
+//! Only the top-level options are touched: an existing option has its value replaced, and a
+//! missing option is added after the last option in the file.
+use std::{fs, ops::Range, path::Path};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::{
+    config::ConfigFormat,
+    params::{self, Params},
+    storage,
+};
+
+/// A top-level option in a config file.
+struct Entry {
+    key: String,
+    /// The span of the whole option, e.g. `(pos #(0 0))` in the flora format, or only its value,
+    /// e.g. `[0, 0]` in JSON.
+    span: Range<usize>,
+}
+
+/// The top-level options of a config file.
+struct Entries {
+    /// The position just after the opening delimiter of the options.
+    start: usize,
+    entries: Vec<Entry>,
+}
+
+/// Sets top-level options in the config file at `path`, adding any that are missing.
+pub fn set_options<T: Serialize>(path: &Path, options: &[(&str, T)]) -> Result<()> {
+    update(path, |format, mut contents| {
+        for (key, value) in options {
+            contents = set_option(format, &contents, key, value)
+                .with_context(|| format!("could not set `{key}` in config {}", path.display()))?;
+        }
+        Ok(contents)
+    })
+}
+
+/// Sets the `params` option in the config file at `path`, adding it if it is missing.
+pub fn set_params(path: &Path, params: &Params) -> Result<()> {
+    update(path, |format, contents| {
+        set_params_option(format, &contents, params)
+            .with_context(|| format!("could not set `params` in config {}", path.display()))
+    })
+}
+
+/// Rewrites the config file at `path` with `f`, which is given its format and contents. The file is
+/// replaced atomically, so a crash can't leave a hand-written config truncated.
+fn update(path: &Path, f: impl FnOnce(ConfigFormat, String) -> Result<String>) -> Result<()> {
+    let format = ConfigFormat::from_path(path)?;
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("could not read config {}", path.display()))?;
+    let contents = f(format, contents)?;
+
+    storage::write_atomically(path, contents.as_bytes())
+        .with_context(|| format!("could not write config {}", path.display()))
+}
+
+/// Sets the `params` option in the contents of a config. In the flora format, params are written
+/// as entries, the way they are read, rather than as serde-lexpr writes maps.
+pub fn set_params_option(format: ConfigFormat, contents: &str, params: &Params) -> Result<String> {
+    match format {
+        ConfigFormat::Flora => {
+            let option = serde_lexpr::Value::cons(
+                serde_lexpr::Value::symbol("params"),
+                params::to_lexpr(params),
+            )
+            .to_string();
+            let entries = flora_entries(contents)?;
+            Ok(splice(contents, &entries, "params", &option, &option, " "))
+        }
+        _ => set_option(format, contents, "params", params),
+    }
+}
+
+/// Sets a top-level option in the contents of a config, adding it if it is missing.
+pub fn set_option<T: Serialize>(
+    format: ConfigFormat,
+    contents: &str,
+    key: &str,
+    value: &T,
+) -> Result<String> {
+    match format {
+        ConfigFormat::Flora => {
+            let option = format!("({key} {})", serde_lexpr::to_string(value)?);
+            let entries = flora_entries(contents)?;
+            Ok(splice(contents, &entries, key, &option, &option, " "))
+        }
+        ConfigFormat::Json => {
+            let value = serde_json::to_string(value)?;
+            let entries = json_entries(contents)?;
+            let member = format!("{}: {value}", serde_json::to_string(key)?);
+            Ok(splice(contents, &entries, key, &value, &member, ", "))
+        }
+        ConfigFormat::Toml => {
+            let mut document: toml_edit::DocumentMut = contents.parse()?;
+            let mut value = value.serialize(toml_edit::ser::ValueSerializer::new())?;
+
+            match document.get_mut(key).and_then(|item| item.as_value_mut()) {
+                Some(existing) => {
+                    *value.decor_mut() = existing.decor().clone();
+                    *existing = value;
+                }
+                None => {
+                    document.insert(key, toml_edit::Item::Value(value));
+                }
+            }
+
+            Ok(document.to_string())
+        }
+    }
+}
+
+/// Replaces the span of the entry with the given key with `replacement`, or inserts `insertion`
+/// after the last entry, separated by `separator` if the entries are all on one line.
+fn splice(
+    contents: &str,
+    entries: &Entries,
+    key: &str,
+    replacement: &str,
+    insertion: &str,
+    separator: &str,
+) -> String {
+    if let Some(entry) = entries.entries.iter().find(|entry| entry.key == key) {
+        return [
+            &contents[..entry.span.start],
+            replacement,
+            &contents[entry.span.end..],
+        ]
+        .concat();
+    }
+
+    let Some(last) = entries.entries.last() else {
+        return [
+            &contents[..entries.start],
+            insertion,
+            &contents[entries.start..],
+        ]
+        .concat();
+    };
+
+    // if the last entry is on its own line, the new entry goes on a new line with the same
+    // indentation. The separator ends with any comma JSON needs between members
+    let line_start = contents[..last.span.start]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let glue = match line_start > entries.start {
+        true => {
+            let line = &contents[line_start..];
+            let indent = &line[..line.len() - line.trim_start().len()];
+            format!("{}\n{indent}", separator.trim_end())
+        }
+        false => separator.to_string(),
+    };
+
+    [
+        &contents[..last.span.end],
+        &glue,
+        insertion,
+        &contents[last.span.end..],
+    ]
+    .concat()
+}
+
+/// A cursor over the contents of a config.
+struct Scanner<'a> {
+    contents: &'a str,
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(contents: &'a str) -> Self {
+        Self {
+            contents,
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.contents[self.position..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        match self.bump() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => bail!(
+                "expected `{expected}` at byte {}, found `{c}`",
+                self.position
+            ),
+            None => bail!("expected `{expected}`, found the end of the config"),
+        }
+    }
+
+    /// Skips whitespace, along with `;` line comments if `comments` is set.
+    fn skip_whitespace(&mut self, comments: bool) {
+        while let Some(c) = self.peek() {
+            match c {
+                ';' if comments => while !matches!(self.bump(), Some('\n') | None) {},
+                c if c.is_whitespace() => {
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Skips a string, starting at its opening quote.
+    fn skip_string(&mut self) -> Result<()> {
+        self.expect('"')?;
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(()),
+                Some('\\') => {
+                    self.bump();
+                }
+                Some(_) => (),
+                None => bail!("unterminated string"),
+            }
+        }
+    }
+
+    /// Skips until one of the delimiters, or whitespace.
+    fn skip_atom(&mut self, delimiters: &[char]) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || delimiters.contains(&c) {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    /// Skips an s-expression datum: a string, a list, a vector or an atom.
+    fn skip_datum(&mut self) -> Result<()> {
+        match self.peek() {
+            Some('"') => self.skip_string(),
+            Some('(') => self.skip_list(),
+            Some('#') if self.contents[self.position..].starts_with("#(") => {
+                self.bump();
+                self.skip_list()
+            }
+            Some(')') => bail!("unexpected `)` at byte {}", self.position),
+            Some(_) => {
+                self.skip_atom(&['(', ')', '"', ';']);
+                Ok(())
+            }
+            None => bail!("unexpected end of the config"),
+        }
+    }
+
+    /// Skips an s-expression list, starting at its opening parenthesis.
+    fn skip_list(&mut self) -> Result<()> {
+        self.expect('(')?;
+        loop {
+            self.skip_whitespace(true);
+            match self.peek() {
+                Some(')') => {
+                    self.bump();
+                    return Ok(());
+                }
+                Some(_) => self.skip_datum()?,
+                None => bail!("unterminated list"),
+            }
+        }
+    }
+
+    /// Skips a JSON value.
+    fn skip_json_value(&mut self) -> Result<()> {
+        match self.peek() {
+            Some('"') => self.skip_string(),
+            Some(open @ ('{' | '[')) => {
+                let close = if open == '{' { '}' } else { ']' };
+                self.bump();
+                loop {
+                    self.skip_whitespace(false);
+                    match self.peek() {
+                        Some(c) if c == close => {
+                            self.bump();
+                            return Ok(());
+                        }
+                        Some(',' | ':') => {
+                            self.bump();
+                        }
+                        Some(_) => self.skip_json_value()?,
+                        None => bail!("unterminated `{open}`"),
+                    }
+                }
+            }
+            Some(_) => {
+                self.skip_atom(&[',', ':', '}', ']']);
+                Ok(())
+            }
+            None => bail!("unexpected end of the config"),
+        }
+    }
+}
+
+/// Finds the top-level options of a config in the flora format, where each option is a list
+/// starting with its key.
+fn flora_entries(contents: &str) -> Result<Entries> {
+    let mut scanner = Scanner::new(contents);
+    scanner.skip_whitespace(true);
+    scanner
+        .expect('(')
+        .context("config does not start with a list of options")?;
+
+    let start = scanner.position;
+    let mut entries = Vec::new();
+    loop {
+        scanner.skip_whitespace(true);
+        let entry_start = scanner.position;
+        match scanner.peek() {
+            Some(')') => break,
+            Some('(') => {
+                scanner.bump();
+                scanner.skip_whitespace(true);
+                let key_start = scanner.position;
+                scanner.skip_atom(&['(', ')', '"', ';']);
+                let key = contents[key_start..scanner.position].to_string();
+
+                scanner.position = entry_start;
+                scanner.skip_list()?;
+                entries.push(Entry {
+                    key,
+                    span: entry_start..scanner.position,
+                });
+            }
+            Some(_) => scanner.skip_datum()?,
+            None => bail!("unterminated list of options"),
+        }
+    }
+
+    Ok(Entries { start, entries })
+}
+
+/// Finds the members of the top-level object of a JSON config.
+fn json_entries(contents: &str) -> Result<Entries> {
+    let mut scanner = Scanner::new(contents);
+    scanner.skip_whitespace(false);
+    scanner
+        .expect('{')
+        .context("config does not start with an object")?;
+
+    let start = scanner.position;
+    let mut entries = Vec::new();
+    loop {
+        scanner.skip_whitespace(false);
+        match scanner.peek() {
+            Some('}') => break,
+            Some(',') => {
+                scanner.bump();
+            }
+            Some('"') => {
+                let key_start = scanner.position;
+                scanner.skip_string()?;
+                let key: String = serde_json::from_str(&contents[key_start..scanner.position])?;
+
+                scanner.skip_whitespace(false);
+                scanner.expect(':')?;
+                scanner.skip_whitespace(false);
+
+                let value_start = scanner.position;
+                scanner.skip_json_value()?;
+                entries.push(Entry {
+                    key,
+                    span: value_start..scanner.position,
+                });
+            }
+            Some(c) => bail!("unexpected `{c}` at byte {}", scanner.position),
+            None => bail!("unterminated object"),
+        }
+    }
+
+    Ok(Entries { start, entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        config::{parse_as, ConfigFormat},
+        layout::Length,
+    };
+
+    use serde_json::json;
+
+    use super::{set_option, set_params_option};
+
+    const POS: (Length, Length) = (Length::Pixels(-5), Length::Percent(12.5));
+
+    #[test]
+    fn test_flora_replace() {
+        let contents = r#"; my clock
+((name "clock") ; the name
+ (pos #(10 10))
+ (content "<p>(pos)</p>"))
+"#;
+
+        assert_eq!(
+            set_option(ConfigFormat::Flora, contents, "pos", &POS).unwrap(),
+            r#"; my clock
+((name "clock") ; the name
+ (pos #(-5 "12.5%"))
+ (content "<p>(pos)</p>"))
+"#
+        );
+    }
+
+    #[test]
+    fn test_flora_insert() {
+        let contents = "((name \"clock\")\n  (content \"hi\")) ; done\n";
+
+        let updated = set_option(ConfigFormat::Flora, contents, "dim", &POS).unwrap();
+        assert_eq!(
+            updated,
+            "((name \"clock\")\n  (content \"hi\")\n  (dim #(-5 \"12.5%\"))) ; done\n"
+        );
+        assert_eq!(
+            parse_as(ConfigFormat::Flora, &updated).unwrap(),
+            parse_as(
+                ConfigFormat::Flora,
+                "((name \"clock\") (content \"hi\") (dim #(-5 \"12.5%\")))"
+            )
+            .unwrap()
+        );
+
+        assert_eq!(
+            set_option(ConfigFormat::Flora, "((name \"a\"))", "pos", &POS).unwrap(),
+            "((name \"a\") (pos #(-5 \"12.5%\")))"
+        );
+        assert_eq!(
+            set_option(ConfigFormat::Flora, "()", "pos", &POS).unwrap(),
+            "((pos #(-5 \"12.5%\")))"
+        );
+    }
+
+    #[test]
+    fn test_set_params() {
+        let serde_json::Value::Object(params) =
+            json!({ "threshold": 90, "location": { "lat": 1.5 } })
+        else {
+            unreachable!()
+        };
+
+        let contents = "((name \"clock\")\n (params (threshold 80)) ; tuned\n (content \"hi\"))\n";
+        let updated = set_params_option(ConfigFormat::Flora, contents, &params).unwrap();
+        assert_eq!(
+            updated,
+            "((name \"clock\")\n (params (location (lat 1.5)) (threshold 90)) ; tuned\n (content \"hi\"))\n"
+        );
+
+        for (format, contents) in [
+            (ConfigFormat::Flora, "((name \"clock\"))"),
+            (ConfigFormat::Toml, "name = \"clock\"\n"),
+            (ConfigFormat::Json, "{ \"name\": \"clock\" }"),
+        ] {
+            let updated = set_params_option(format, contents, &params).unwrap();
+            assert_eq!(
+                parse_as(format, &updated).unwrap().params(),
+                params,
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_replace_and_insert() {
+        let contents = r#"{
+    "name": "clock",
+    "pos": [
+        10,
+        10
+    ],
+    "content": "{\"pos\": 1}"
+}
+"#;
+
+        let updated = set_option(ConfigFormat::Json, contents, "pos", &POS).unwrap();
+        assert_eq!(
+            updated,
+            r#"{
+    "name": "clock",
+    "pos": [-5,"12.5%"],
+    "content": "{\"pos\": 1}"
+}
+"#
+        );
+
+        assert_eq!(
+            set_option(ConfigFormat::Json, &updated, "dim", &POS).unwrap(),
+            r#"{
+    "name": "clock",
+    "pos": [-5,"12.5%"],
+    "content": "{\"pos\": 1}",
+    "dim": [-5,"12.5%"]
+}
+"#
+        );
+
+        assert_eq!(
+            set_option(ConfigFormat::Json, "{}", "pos", &POS).unwrap(),
+            r#"{"pos": [-5,"12.5%"]}"#
+        );
+    }
+
+    #[test]
+    fn test_toml_replace_and_insert() {
+        let contents = r#"# my clock
+name = "clock"
+pos = [10, 10] # top left
+content = "hi"
+"#;
+
+        let updated = set_option(ConfigFormat::Toml, contents, "pos", &POS).unwrap();
+        assert_eq!(
+            updated,
+            r#"# my clock
+name = "clock"
+pos = [-5, "12.5%"] # top left
+content = "hi"
+"#
+        );
+
+        let updated = set_option(ConfigFormat::Toml, &updated, "dim", &POS).unwrap();
+        assert!(updated.ends_with("content = \"hi\"\ndim = [-5, \"12.5%\"]\n"));
+    }
+
+    #[test]
+    fn test_invalid_configs() {
+        assert!(set_option(ConfigFormat::Flora, "(name \"a\"", "pos", &POS).is_err());
+        assert!(set_option(ConfigFormat::Flora, "name", "pos", &POS).is_err());
+        assert!(set_option(ConfigFormat::Json, "[1, 2]", "pos", &POS).is_err());
+        assert!(set_option(ConfigFormat::Json, "{\"name\": \"a", "pos", &POS).is_err());
+        assert!(set_option(ConfigFormat::Toml, "name = ", "pos", &POS).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+//! Conversions between device independent pixels (DIPs) and physical pixels.
+//!
+//! A DIP is one physical pixel on a 96 DPI (100% scale) display, so a length in DIPs keeps the
+//! same apparent size across displays with different scale factors.
+
+/// The DPI of a display at 100% scale.
+pub const DEFAULT_DPI: u32 = 96;
+
+/// Returns the scale factor of a display with the given DPI, e.g. `1.5` for 150% scale.
+pub fn scale_factor(dpi: u32) -> f64 {
+    dpi as f64 / DEFAULT_DPI as f64
+}
+
+/// Converts a length in DIPs to physical pixels on a display with the given DPI.
+pub fn to_physical(dips: f64, dpi: u32) -> i32 {
+    (dips * scale_factor(dpi)).round() as i32
+}
+
+/// Converts a length in physical pixels on a display with the given DPI to DIPs.
+pub fn to_logical(pixels: i32, dpi: u32) -> f64 {
+    pixels as f64 / scale_factor(dpi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scale_factor, to_logical, to_physical, DEFAULT_DPI};
+
+    #[test]
+    fn test_scale_factor() {
+        assert_eq!(scale_factor(DEFAULT_DPI), 1.0);
+        assert_eq!(scale_factor(144), 1.5);
+        assert_eq!(scale_factor(192), 2.0);
+    }
+
+    #[test]
+    fn test_to_physical() {
+        assert_eq!(to_physical(200.0, DEFAULT_DPI), 200);
+        assert_eq!(to_physical(200.0, 192), 400);
+        assert_eq!(to_physical(-10.0, 144), -15);
+        // 125% scale, where lengths do not divide evenly
+        assert_eq!(to_physical(15.0, 120), 19);
+        assert_eq!(to_physical(0.5, 96), 1);
+    }
+
+    #[test]
+    fn test_to_logical() {
+        assert_eq!(to_logical(200, DEFAULT_DPI), 200.0);
+        assert_eq!(to_logical(400, 192), 200.0);
+        assert_eq!(to_logical(-15, 144), -10.0);
+    }
+}
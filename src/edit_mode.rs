@@ -0,0 +1,162 @@
+//! Edit mode, where a widget can be moved and resized with the mouse.
+//!
+//! While in edit mode, [`EDIT_MODE_SCRIPT`] covers the page with an overlay. Pressing the mouse on
+//! the overlay starts moving the window, or resizing it when pressed near an edge. Pressing Enter
+//! or Escape ends edit mode.
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::snap::Edges;
+
+/// The name of the binding the overlay starts moving or resizing the window through.
+pub const BEGIN_DRAG_BINDING: &str = "__floraBeginDrag";
+/// The name of the binding the overlay ends edit mode through.
+pub const END_EDIT_MODE_BINDING: &str = "__floraEndEditMode";
+
+/// Defines `window.__floraSetEditMode`, which shows or hides the edit mode overlay.
+pub const EDIT_MODE_SCRIPT: &str = r#"
+(function() {
+    var EDGE = 8;
+    var CURSORS = {
+        "move": "move",
+        "left": "ew-resize",
+        "right": "ew-resize",
+        "top": "ns-resize",
+        "bottom": "ns-resize",
+        "top-left": "nwse-resize",
+        "bottom-right": "nwse-resize",
+        "top-right": "nesw-resize",
+        "bottom-left": "nesw-resize",
+    };
+    var overlay = null;
+
+    function region(event) {
+        var vertical = event.clientY < EDGE ? "top"
+            : event.clientY >= window.innerHeight - EDGE ? "bottom" : "";
+        var horizontal = event.clientX < EDGE ? "left"
+            : event.clientX >= window.innerWidth - EDGE ? "right" : "";
+        if (vertical && horizontal) {
+            return vertical + "-" + horizontal;
+        }
+        return vertical || horizontal || "move";
+    }
+
+    window.__floraSetEditMode = function(enabled) {
+        if (overlay) {
+            overlay.remove();
+            overlay = null;
+        }
+        if (!enabled) {
+            return;
+        }
+
+        overlay = document.createElement("div");
+        overlay.tabIndex = -1;
+        overlay.style.cssText = "position: fixed; inset: 0; z-index: 2147483647;"
+            + " box-sizing: border-box; border: 2px dashed #3b82f6;"
+            + " background: rgba(59, 130, 246, 0.15); outline: none;";
+        overlay.addEventListener("mousemove", function(event) {
+            overlay.style.cursor = CURSORS[region(event)];
+        });
+        overlay.addEventListener("mousedown", function(event) {
+            if (event.button === 0) {
+                event.preventDefault();
+                window.__floraBeginDrag(region(event));
+            }
+        });
+        overlay.addEventListener("keydown", function(event) {
+            if (event.key === "Enter" || event.key === "Escape") {
+                window.__floraEndEditMode();
+            }
+        });
+        document.documentElement.appendChild(overlay);
+        overlay.focus();
+    };
+})();
+"#;
+
+// see WM_SYSCOMMAND and WM_SIZING
+const SC_SIZE: usize = 0xF000;
+const SC_MOVE: usize = 0xF010;
+const HTCAPTION: usize = 2;
+const WMSZ_LEFT: usize = 1;
+const WMSZ_RIGHT: usize = 2;
+const WMSZ_TOP: usize = 3;
+const WMSZ_TOPLEFT: usize = 4;
+const WMSZ_TOPRIGHT: usize = 5;
+const WMSZ_BOTTOM: usize = 6;
+const WMSZ_BOTTOMLEFT: usize = 7;
+const WMSZ_BOTTOMRIGHT: usize = 8;
+
+/// The part of the widget the mouse was pressed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum DragRegion {
+    Move,
+    Left,
+    Top,
+    Right,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl DragRegion {
+    /// Returns the `WM_SYSCOMMAND` command that starts moving or resizing the window from this
+    /// region.
+    pub fn syscommand(&self) -> usize {
+        match self {
+            Self::Move => SC_MOVE | HTCAPTION,
+            Self::Left => SC_SIZE | WMSZ_LEFT,
+            Self::Top => SC_SIZE | WMSZ_TOP,
+            Self::Right => SC_SIZE | WMSZ_RIGHT,
+            Self::Bottom => SC_SIZE | WMSZ_BOTTOM,
+            Self::TopLeft => SC_SIZE | WMSZ_TOPLEFT,
+            Self::TopRight => SC_SIZE | WMSZ_TOPRIGHT,
+            Self::BottomLeft => SC_SIZE | WMSZ_BOTTOMLEFT,
+            Self::BottomRight => SC_SIZE | WMSZ_BOTTOMRIGHT,
+        }
+    }
+}
+
+/// Returns the edges being moved for the `WMSZ_*` edge passed with `WM_SIZING`.
+pub fn sizing_edges(edge: usize) -> Edges {
+    Edges {
+        left: matches!(edge, WMSZ_LEFT | WMSZ_TOPLEFT | WMSZ_BOTTOMLEFT),
+        top: matches!(edge, WMSZ_TOP | WMSZ_TOPLEFT | WMSZ_TOPRIGHT),
+        right: matches!(edge, WMSZ_RIGHT | WMSZ_TOPRIGHT | WMSZ_BOTTOMRIGHT),
+        bottom: matches!(edge, WMSZ_BOTTOM | WMSZ_BOTTOMLEFT | WMSZ_BOTTOMRIGHT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snap::Edges;
+
+    use super::{sizing_edges, DragRegion, SC_SIZE};
+
+    #[test]
+    fn test_region_round_trip() {
+        let region: DragRegion = serde_json::from_str(r#""top-right""#).unwrap();
+        assert_eq!(region, DragRegion::TopRight);
+
+        // resizing from a region moves the edges of that region
+        assert_eq!(
+            sizing_edges(region.syscommand() - SC_SIZE),
+            Edges {
+                top: true,
+                right: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            sizing_edges(DragRegion::Bottom.syscommand() - SC_SIZE),
+            Edges {
+                bottom: true,
+                ..Default::default()
+            }
+        );
+    }
+}
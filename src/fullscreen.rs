@@ -0,0 +1,115 @@
+//! Detects applications running fullscreen on the monitor of the widget, e.g. games, videos and
+//! presentations, so the widget can get out of their way.
+use crate::layout::Rect;
+
+/// Window classes that are never considered fullscreen applications, even though they cover the
+/// monitor: the desktop, the taskbar, and flora widgets themselves.
+const EXCLUDED_CLASSES: [&str; 5] = [
+    "Progman",
+    "WorkerW",
+    "Shell_TrayWnd",
+    "Shell_SecondaryTrayWnd",
+    "flora",
+];
+
+/// The foreground window, as seen when checking for fullscreen applications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundWindow {
+    /// The name of the window class.
+    pub class: String,
+    /// The bounds of the window in screen coordinates.
+    pub rect: Rect,
+    /// Whether the window is maximized.
+    pub maximized: bool,
+}
+
+/// Whether the window is a fullscreen application on the given monitor, i.e. it covers the whole
+/// monitor, including the taskbar. Maximized windows are not fullscreen, even though they can
+/// cover the monitor when the taskbar hides itself.
+pub fn is_fullscreen(window: &ForegroundWindow, monitor: &Rect) -> bool {
+    if window.maximized || EXCLUDED_CLASSES.contains(&window.class.as_str()) {
+        return false;
+    }
+    if monitor.width <= 0 || monitor.height <= 0 {
+        return false;
+    }
+
+    let rect = &window.rect;
+    rect.x <= monitor.x
+        && rect.y <= monitor.y
+        && rect.x + rect.width >= monitor.x + monitor.width
+        && rect.y + rect.height >= monitor.y + monitor.height
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::Rect;
+
+    use super::{is_fullscreen, ForegroundWindow};
+
+    const MONITOR: Rect = Rect {
+        x: 1920,
+        y: 0,
+        width: 2560,
+        height: 1440,
+    };
+
+    fn window(class: &str, rect: Rect) -> ForegroundWindow {
+        ForegroundWindow {
+            class: class.to_string(),
+            rect,
+            maximized: false,
+        }
+    }
+
+    #[test]
+    fn test_fullscreen_window() {
+        assert!(is_fullscreen(&window("UnityWndClass", MONITOR), &MONITOR));
+
+        // windows with invisible borders extend past the monitor
+        let bordered = Rect {
+            x: MONITOR.x - 8,
+            y: -8,
+            width: MONITOR.width + 16,
+            height: MONITOR.height + 16,
+        };
+        assert!(is_fullscreen(
+            &window("Chrome_WidgetWin_1", bordered),
+            &MONITOR
+        ));
+    }
+
+    #[test]
+    fn test_not_fullscreen() {
+        // leaves room for the taskbar
+        let work_area = Rect {
+            height: MONITOR.height - 48,
+            ..MONITOR
+        };
+        assert!(!is_fullscreen(&window("Notepad", work_area), &MONITOR));
+
+        // fullscreen, but on another monitor
+        let primary = Rect {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        assert!(!is_fullscreen(&window("UnityWndClass", primary), &MONITOR));
+
+        // maximized with an auto-hiding taskbar
+        let maximized = ForegroundWindow {
+            maximized: true,
+            ..window("Notepad", MONITOR)
+        };
+        assert!(!is_fullscreen(&maximized, &MONITOR));
+    }
+
+    #[test]
+    fn test_excluded_classes() {
+        for class in ["Progman", "WorkerW", "Shell_TrayWnd", "flora"] {
+            assert!(!is_fullscreen(&window(class, MONITOR), &MONITOR), "{class}");
+        }
+        assert!(!is_fullscreen(&window("App", MONITOR), &Rect::default()));
+    }
+}
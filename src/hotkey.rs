@@ -0,0 +1,407 @@
+//! Global hotkeys, declared in configs as `(hotkey "Ctrl+Alt+C" toggle)`.
+//!
+//! Hotkeys are registered by the widget process, and trigger the same actions the pipe server
+//! handles for the CLI.
+use std::{fmt, str::FromStr};
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::pipe::protocol::ServerRequest;
+
+// see RegisterHotKey
+pub const MOD_ALT: u32 = 0x1;
+pub const MOD_CONTROL: u32 = 0x2;
+pub const MOD_SHIFT: u32 = 0x4;
+pub const MOD_WIN: u32 = 0x8;
+
+/// The modifiers in the order they are displayed, along with the names they can be written as.
+const MODIFIERS: [(u32, &[&str]); 4] = [
+    (MOD_CONTROL, &["ctrl", "control"]),
+    (MOD_ALT, &["alt"]),
+    (MOD_SHIFT, &["shift"]),
+    (MOD_WIN, &["win", "super", "meta"]),
+];
+
+/// Named keys and their virtual-key codes. The first name of each key is the one it is displayed
+/// as.
+const NAMED_KEYS: [(&str, u32); 40] = [
+    ("Backspace", 0x08),
+    ("Tab", 0x09),
+    ("Enter", 0x0D),
+    ("Return", 0x0D),
+    ("Pause", 0x13),
+    ("CapsLock", 0x14),
+    ("Escape", 0x1B),
+    ("Esc", 0x1B),
+    ("Space", 0x20),
+    ("PageUp", 0x21),
+    ("PgUp", 0x21),
+    ("PageDown", 0x22),
+    ("PgDn", 0x22),
+    ("End", 0x23),
+    ("Home", 0x24),
+    ("Left", 0x25),
+    ("Up", 0x26),
+    ("Right", 0x27),
+    ("Down", 0x28),
+    ("PrintScreen", 0x2C),
+    ("Insert", 0x2D),
+    ("Ins", 0x2D),
+    ("Delete", 0x2E),
+    ("Del", 0x2E),
+    (";", 0xBA),
+    ("=", 0xBB),
+    ("Plus", 0xBB),
+    (",", 0xBC),
+    ("Comma", 0xBC),
+    ("-", 0xBD),
+    ("Minus", 0xBD),
+    (".", 0xBE),
+    ("Period", 0xBE),
+    ("/", 0xBF),
+    ("`", 0xC0),
+    ("[", 0xDB),
+    ("\\", 0xDC),
+    ("]", 0xDD),
+    ("'", 0xDE),
+    ("Quote", 0xDE),
+];
+
+const VK_F1: u32 = 0x70;
+const VK_F24: u32 = 0x87;
+const VK_NUMPAD0: u32 = 0x60;
+
+/// A key combination, such as `Ctrl+Alt+C`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Chord {
+    /// The `MOD_*` flags of the modifiers.
+    pub modifiers: u32,
+    /// The virtual-key code of the key.
+    pub key: u32,
+}
+
+/// Parses the name of a key, ignoring case, into its virtual-key code.
+fn parse_key(name: &str) -> Option<u32> {
+    if let Some((_, key)) = NAMED_KEYS
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+    {
+        return Some(*key);
+    }
+
+    let upper = name.to_ascii_uppercase();
+    match upper.as_bytes() {
+        [c] if c.is_ascii_alphanumeric() => return Some(*c as u32),
+        _ => (),
+    }
+    if let Some(n) = upper.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+        return (1..=24).contains(&n).then_some(VK_F1 + n - 1);
+    }
+    if let Some(n) = upper
+        .strip_prefix("NUMPAD")
+        .and_then(|n| n.parse::<u32>().ok())
+    {
+        return (n <= 9).then_some(VK_NUMPAD0 + n);
+    }
+
+    None
+}
+
+/// Returns the name a virtual-key code is displayed as.
+fn key_name(key: u32) -> String {
+    match key {
+        0x30..=0x39 | 0x41..=0x5A => char::from(key as u8).to_string(),
+        VK_F1..=VK_F24 => format!("F{}", key - VK_F1 + 1),
+        VK_NUMPAD0..=0x69 => format!("Numpad{}", key - VK_NUMPAD0),
+        _ => NAMED_KEYS
+            .iter()
+            .find(|(_, code)| *code == key)
+            .map_or_else(|| format!("{key:#04x}"), |(name, _)| name.to_string()),
+    }
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| format!("invalid hotkey `{s}`: {reason}");
+
+        // `+` separates the keys, so a trailing `++` is the plus key itself
+        let (rest, plus) = match s.trim().strip_suffix("++") {
+            Some(rest) => (rest, true),
+            None => (s.trim(), false),
+        };
+        if rest.is_empty() && !plus {
+            return Err(invalid("the hotkey is empty".to_string()));
+        }
+
+        let mut modifiers = 0;
+        let mut key: Option<(&str, u32)> = plus.then_some(("+", 0xBB));
+        let parts = match rest.is_empty() {
+            true => Vec::new(),
+            false => rest.split('+').map(str::trim).collect(),
+        };
+
+        for part in parts {
+            if part.is_empty() {
+                return Err(invalid("expected a key between each `+`".to_string()));
+            }
+
+            let modifier = MODIFIERS
+                .iter()
+                .find(|(_, names)| names.iter().any(|name| name.eq_ignore_ascii_case(part)));
+            if let Some((modifier, _)) = modifier {
+                if modifiers & modifier != 0 {
+                    return Err(invalid(format!("`{part}` is given more than once")));
+                }
+                modifiers |= modifier;
+                continue;
+            }
+
+            let code = parse_key(part).ok_or_else(|| {
+                invalid(format!(
+                    "`{part}` is not a key or a modifier (Ctrl, Alt, Shift or Win)"
+                ))
+            })?;
+            if let Some((other, _)) = key {
+                return Err(invalid(format!(
+                    "only one key can be used besides the modifiers, but found `{other}` and \
+                     `{part}`"
+                )));
+            }
+            key = Some((part, code));
+        }
+
+        let Some((_, key)) = key else {
+            return Err(invalid("expected a key after the modifiers".to_string()));
+        };
+        // a global hotkey without modifiers would swallow the key in every other application
+        if modifiers == 0 && !(VK_F1..=VK_F24).contains(&key) {
+            return Err(invalid(
+                "at least one modifier is required, unless the key is a function key".to_string(),
+            ));
+        }
+
+        Ok(Chord { modifiers, key })
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, names) in MODIFIERS {
+            if self.modifiers & modifier != 0 {
+                // the first name, capitalized
+                let name = names[0];
+                write!(f, "{}{}+", name[..1].to_ascii_uppercase(), &name[1..])?;
+            }
+        }
+        write!(f, "{}", key_name(self.key))
+    }
+}
+
+impl Serialize for Chord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Chord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for Chord {
+    fn schema_name() -> String {
+        "Chord".to_string()
+    }
+
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(
+                    "A key combination, such as \"Ctrl+Alt+C\" or \"Win+Shift+F5\"".to_string(),
+                ),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// What a hotkey does to the widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotkeyAction {
+    /// Shows the widget if it is hidden, and hides it otherwise.
+    Toggle,
+    /// Shows the widget.
+    Show,
+    /// Hides the widget.
+    Hide,
+    /// Loads the content of the widget again.
+    Reload,
+    /// Enters or leaves edit mode.
+    Edit,
+    /// Shows the settings page.
+    Settings,
+}
+
+impl HotkeyAction {
+    /// Returns the pipe server request performing this action.
+    pub fn request(&self) -> ServerRequest {
+        match self {
+            Self::Toggle => ServerRequest::ToggleWindow,
+            Self::Show => ServerRequest::ShowWindow,
+            Self::Hide => ServerRequest::HideWindow,
+            Self::Reload => ServerRequest::Reload,
+            Self::Edit => ServerRequest::ToggleEditMode,
+            Self::Settings => ServerRequest::OpenSettings,
+        }
+    }
+}
+
+/// A hotkey and the action it triggers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Hotkey(pub Chord, pub HotkeyAction);
+
+/// Returns the chords that are bound more than once, in the order they are first bound.
+pub fn duplicates(hotkeys: &[Hotkey]) -> Vec<Chord> {
+    let mut duplicates = Vec::new();
+    for (i, Hotkey(chord, _)) in hotkeys.iter().enumerate() {
+        if hotkeys[..i].iter().any(|Hotkey(other, _)| other == chord) && !duplicates.contains(chord)
+        {
+            duplicates.push(*chord);
+        }
+    }
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{duplicates, Chord, Hotkey, HotkeyAction, MOD_ALT, MOD_CONTROL, MOD_WIN};
+
+    fn chord(s: &str) -> Chord {
+        s.parse().unwrap()
+    }
+
+    fn error(s: &str) -> String {
+        s.parse::<Chord>().unwrap_err()
+    }
+
+    #[test]
+    fn test_parse_chord() {
+        assert_eq!(
+            chord("Ctrl+Alt+C"),
+            Chord {
+                modifiers: MOD_CONTROL | MOD_ALT,
+                key: 'C' as u32
+            }
+        );
+        // case, whitespace and order do not matter
+        assert_eq!(chord(" shift + win + r "), chord("Win+Shift+R"));
+        assert_eq!(chord("Super+Control+1").modifiers, MOD_WIN | MOD_CONTROL);
+        assert_eq!(chord("Alt+F4").key, 0x73);
+        assert_eq!(
+            chord("F13"),
+            Chord {
+                modifiers: 0,
+                key: 0x7C
+            }
+        );
+        assert_eq!(chord("Ctrl+Numpad7").key, 0x67);
+        assert_eq!(chord("Ctrl+PgDn"), chord("Ctrl+PageDown"));
+        assert_eq!(chord("Ctrl+Shift+/").key, 0xBF);
+        assert_eq!(
+            chord("Ctrl++"),
+            Chord {
+                modifiers: MOD_CONTROL,
+                key: 0xBB
+            }
+        );
+    }
+
+    #[test]
+    fn test_display_chord() {
+        for (input, displayed) in [
+            ("alt+ctrl+c", "Ctrl+Alt+C"),
+            ("Win+Shift+Esc", "Shift+Win+Escape"),
+            ("Ctrl+Alt+Shift+Win+F12", "Ctrl+Alt+Shift+Win+F12"),
+            ("Ctrl++", "Ctrl+="),
+            ("Ctrl+numpad0", "Ctrl+Numpad0"),
+        ] {
+            assert_eq!(chord(input).to_string(), displayed);
+            assert_eq!(chord(displayed), chord(input));
+        }
+    }
+
+    #[test]
+    fn test_chord_errors() {
+        assert_eq!(error(""), "invalid hotkey ``: the hotkey is empty");
+        assert_eq!(
+            error("Ctrl+Alt"),
+            "invalid hotkey `Ctrl+Alt`: expected a key after the modifiers"
+        );
+        assert_eq!(
+            error("Ctrl+Ctrl+C"),
+            "invalid hotkey `Ctrl+Ctrl+C`: `Ctrl` is given more than once"
+        );
+        assert_eq!(
+            error("Ctrl+Hyper+C"),
+            "invalid hotkey `Ctrl+Hyper+C`: `Hyper` is not a key or a modifier (Ctrl, Alt, Shift \
+             or Win)"
+        );
+        assert_eq!(
+            error("Ctrl+A+B"),
+            "invalid hotkey `Ctrl+A+B`: only one key can be used besides the modifiers, but found \
+             `A` and `B`"
+        );
+        assert_eq!(
+            error("Ctrl++C"),
+            "invalid hotkey `Ctrl++C`: expected a key between each `+`"
+        );
+        assert_eq!(
+            error("C"),
+            "invalid hotkey `C`: at least one modifier is required, unless the key is a function \
+             key"
+        );
+        assert!(error("Ctrl+F25").contains("`F25` is not a key"));
+    }
+
+    #[test]
+    fn test_deserialize_hotkeys() {
+        let hotkeys: Vec<Hotkey> =
+            serde_json::from_str(r#"[["Ctrl+Alt+C", "toggle"], ["Win+Shift+R", "reload"]]"#)
+                .unwrap();
+        assert_eq!(
+            hotkeys,
+            [
+                Hotkey(chord("Ctrl+Alt+C"), HotkeyAction::Toggle),
+                Hotkey(chord("Win+Shift+R"), HotkeyAction::Reload),
+            ]
+        );
+
+        let err = serde_json::from_str::<Vec<Hotkey>>(r#"[["Ctrl+", "toggle"]]"#).unwrap_err();
+        assert!(err.to_string().contains("invalid hotkey `Ctrl+`"), "{err}");
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let hotkeys = [
+            Hotkey(chord("Ctrl+Alt+C"), HotkeyAction::Toggle),
+            Hotkey(chord("Ctrl+Shift+E"), HotkeyAction::Edit),
+            Hotkey(chord("alt+ctrl+c"), HotkeyAction::Reload),
+            Hotkey(chord("Ctrl+Alt+C"), HotkeyAction::Hide),
+        ];
+        assert_eq!(duplicates(&hotkeys), [chord("Ctrl+Alt+C")]);
+        assert_eq!(duplicates(&hotkeys[..2]), []);
+    }
+}
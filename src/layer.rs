@@ -0,0 +1,29 @@
+//! The z-order layers a widget window can be placed in.
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The z-order layer of the widget window.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Layer {
+    /// Above all other non-topmost windows.
+    Topmost,
+    /// Ordered like any other window.
+    #[default]
+    Normal,
+    /// Below all other windows.
+    Bottom,
+    /// Below all other windows and attached to the desktop, so the widget stays visible when
+    /// showing the desktop (Win+D).
+    Desktop,
+}
+
+impl Layer {
+    /// Whether the window must be kept below all other windows.
+    pub fn is_pinned_to_bottom(&self) -> bool {
+        matches!(self, Self::Bottom | Self::Desktop)
+    }
+}
@@ -0,0 +1,884 @@
+//! Resolves the configured widget geometry into a window rect.
+//!
+//! Everything here is pure, taking the geometry of the monitor the widget is placed on and
+//! producing the rect (in virtual screen coordinates) the window should occupy.
+use std::fmt;
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject, StringValidation, SubschemaValidation},
+    JsonSchema,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{auto_size, dpi};
+
+/// A rect in screen coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    /// Whether the point is inside the rect. The right and bottom edges are exclusive.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// The point of the monitor a widget is positioned relative to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// Where along an axis a widget is anchored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alignment {
+    Start,
+    Center,
+    End,
+}
+
+impl Anchor {
+    fn horizontal(&self) -> Alignment {
+        match self {
+            Self::TopLeft | Self::Left | Self::BottomLeft => Alignment::Start,
+            Self::Top | Self::Center | Self::Bottom => Alignment::Center,
+            Self::TopRight | Self::Right | Self::BottomRight => Alignment::End,
+        }
+    }
+
+    fn vertical(&self) -> Alignment {
+        match self {
+            Self::TopLeft | Self::Top | Self::TopRight => Alignment::Start,
+            Self::Left | Self::Center | Self::Right => Alignment::Center,
+            Self::BottomLeft | Self::Bottom | Self::BottomRight => Alignment::End,
+        }
+    }
+}
+
+/// A length along one axis of a monitor.
+///
+/// In configs, lengths are either an integer number of physical pixels (`200`) or a string with a
+/// unit (`"200px"`, `"200dip"`, `"50%"`). DIPs are scaled by the DPI of the monitor, and
+/// percentages are relative to the size of the monitor along the same axis. Dimensions can also be
+/// `auto`, in which case the widget is sized to its content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Pixels(i32),
+    Dip(f64),
+    Percent(f64),
+    Auto,
+}
+
+impl Length {
+    /// Resolves the length to physical pixels, where `extent` is the size of the monitor along
+    /// the same axis and `dpi` is the DPI of the monitor. Auto lengths resolve to 0, since only
+    /// [`resolve`] knows the size of the content.
+    pub fn resolve(&self, extent: i32, dpi: u32) -> i32 {
+        match self {
+            Self::Pixels(px) => *px,
+            Self::Dip(dips) => dpi::to_physical(*dips, dpi),
+            Self::Percent(percent) => (extent as f64 * percent / 100.0).round() as i32,
+            Self::Auto => 0,
+        }
+    }
+
+    /// Returns a length in the same unit as this one that resolves to `pixels`. Lengths that
+    /// already resolve to `pixels` are returned unchanged, and converted lengths are rounded to two
+    /// decimal places. Auto lengths stay auto, since they follow the content.
+    pub fn with_pixels(&self, pixels: i32, extent: i32, dpi: u32) -> Length {
+        let round = |value: f64| (value * 100.0).round() / 100.0;
+
+        if *self == Self::Auto || self.resolve(extent, dpi) == pixels {
+            return *self;
+        }
+        match self {
+            Self::Dip(_) => Self::Dip(round(dpi::to_logical(pixels, dpi))),
+            Self::Percent(_) if extent != 0 => {
+                Self::Percent(round(pixels as f64 * 100.0 / extent as f64))
+            }
+            _ => Self::Pixels(pixels),
+        }
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pixels(px) => write!(f, "{px}px"),
+            Self::Dip(dips) => write!(f, "{dips}dip"),
+            Self::Percent(percent) => write!(f, "{percent}%"),
+            Self::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+impl std::str::FromStr for Length {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "auto" {
+            return Ok(Self::Auto);
+        }
+        if let Some(percent) = s.strip_suffix('%') {
+            return match percent.trim().parse::<f64>() {
+                Ok(percent) if percent.is_finite() => Ok(Self::Percent(percent)),
+                _ => Err(format!("invalid percentage `{s}`")),
+            };
+        }
+        if let Some(dips) = s.strip_suffix("dip") {
+            return match dips.trim().parse::<f64>() {
+                Ok(dips) if dips.is_finite() => Ok(Self::Dip(dips)),
+                _ => Err(format!("invalid length in DIPs `{s}`")),
+            };
+        }
+
+        s.strip_suffix("px")
+            .unwrap_or(s)
+            .trim()
+            .parse::<i32>()
+            .map(Self::Pixels)
+            .map_err(|_| {
+                format!(
+                    "invalid length `{s}`, expected e.g. `200`, `\"200dip\"`, `\"50%\"` or `auto`"
+                )
+            })
+    }
+}
+
+impl Serialize for Length {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Pixels(px) => serializer.serialize_i32(*px),
+            Self::Dip(_) | Self::Percent(_) | Self::Auto => {
+                serializer.serialize_str(&self.to_string())
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LengthVisitor;
+
+        impl de::Visitor<'_> for LengthVisitor {
+            type Value = Length;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(
+                    f,
+                    "an integer number of pixels, a string such as \"50%\", or auto"
+                )
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Length, E> {
+                i32::try_from(v)
+                    .map(Length::Pixels)
+                    .map_err(|_| E::custom(format!("length {v} is out of range")))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Length, E> {
+                i32::try_from(v)
+                    .map(Length::Pixels)
+                    .map_err(|_| E::custom(format!("length {v} is out of range")))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Length, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(LengthVisitor)
+    }
+}
+
+/// Returns the pattern of lengths with a unit in the schema, matching what [`Length`] parses:
+/// pixels are integers, while DIPs and percentages may have fractions and exponents.
+fn length_pattern(auto: bool) -> String {
+    let units =
+        r"[+-]?[0-9]+(\s*px)?|[+-]?([0-9]+(\.[0-9]*)?|\.[0-9]+)([eE][+-]?[0-9]+)?\s*(dip|%)";
+    match auto {
+        true => format!(r"^\s*({units}|auto)\s*$"),
+        false => format!(r"^\s*({units})\s*$"),
+    }
+}
+
+/// Returns the schema of lengths, which may be `auto` if `auto` is set.
+fn length_schema(gen: &mut SchemaGenerator, auto: bool, description: &str) -> Schema {
+    let unit = SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        string: Some(Box::new(StringValidation {
+            pattern: Some(length_pattern(auto)),
+            ..Default::default()
+        })),
+        ..Default::default()
+    };
+
+    SchemaObject {
+        metadata: Some(Box::new(Metadata {
+            description: Some(description.to_string()),
+            ..Default::default()
+        })),
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![gen.subschema_for::<i32>(), unit.into()]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+impl JsonSchema for Length {
+    fn schema_name() -> String {
+        "Length".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        length_schema(
+            gen,
+            true,
+            "A number of pixels, a string with a unit such as \"200dip\" or \"50%\", or \"auto\" \
+             to size a dimension to the content",
+        )
+    }
+}
+
+/// A [`Length`] in the `pos` option, which can't be `auto`. Only used for its schema.
+struct Offset;
+
+impl JsonSchema for Offset {
+    fn schema_name() -> String {
+        "Offset".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        length_schema(
+            gen,
+            false,
+            "A number of pixels, or a string with a unit such as \"200dip\" or \"50%\"",
+        )
+    }
+}
+
+/// The configured geometry of a widget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    /// The point of the monitor the widget is positioned relative to.
+    pub anchor: Anchor,
+    /// The offset of the widget from its anchor. Offsets move the widget away from the anchored
+    /// edges, i.e. a positive x offset moves a right-anchored widget to the left.
+    pub position: (Length, Length),
+    /// The size of the widget. Auto dimensions follow the size of the content.
+    pub dimension: (Length, Length),
+    /// The smallest size of auto dimensions.
+    pub min_dimension: Option<(Length, Length)>,
+    /// The largest size of auto dimensions.
+    pub max_dimension: Option<(Length, Length)>,
+    /// The measured size of the page in DIPs, if it has been measured yet.
+    pub content_size: Option<(f64, f64)>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            anchor: Anchor::default(),
+            position: (Length::Pixels(0), Length::Pixels(0)),
+            dimension: (Length::Pixels(200), Length::Pixels(20)),
+            min_dimension: None,
+            max_dimension: None,
+            content_size: None,
+        }
+    }
+}
+
+impl Layout {
+    /// Whether the width and height of the widget follow the size of the content.
+    pub fn auto_size(&self) -> (bool, bool) {
+        (
+            self.dimension.0 == Length::Auto,
+            self.dimension.1 == Length::Auto,
+        )
+    }
+}
+
+/// The size of auto dimensions before the content has been measured.
+const UNMEASURED_SIZE: i32 = 100;
+
+/// Resolves a dimension of the widget, where `content` is the measured size of the content along
+/// the same axis. Auto dimensions are sized to the content, limited to `min` and `max`.
+fn resolve_dimension(
+    length: &Length,
+    content: Option<i32>,
+    (min, max): (Option<&Length>, Option<&Length>),
+    extent: i32,
+    dpi: u32,
+) -> i32 {
+    if *length != Length::Auto {
+        return length.resolve(extent, dpi);
+    }
+
+    let limit = |length: Option<&Length>| {
+        length
+            .filter(|length| **length != Length::Auto)
+            .map(|length| length.resolve(extent, dpi))
+    };
+    let mut size = content.unwrap_or(UNMEASURED_SIZE);
+    if let Some(max) = limit(max) {
+        size = size.min(max);
+    }
+    if let Some(min) = limit(min) {
+        size = size.max(min);
+    }
+    size
+}
+
+/// Resolves the layout into the rect the window should occupy on the given monitor, where `dpi` is
+/// the DPI of the monitor.
+pub fn resolve(layout: &Layout, monitor: &Rect, dpi: u32) -> Rect {
+    let (min, max) = (layout.min_dimension.as_ref(), layout.max_dimension.as_ref());
+    let content_size = layout
+        .content_size
+        .map(|size| auto_size::to_physical_size(size, dpi));
+    let width = resolve_dimension(
+        &layout.dimension.0,
+        content_size.map(|size| size.0),
+        (min.map(|min| &min.0), max.map(|max| &max.0)),
+        monitor.width,
+        dpi,
+    );
+    let height = resolve_dimension(
+        &layout.dimension.1,
+        content_size.map(|size| size.1),
+        (min.map(|min| &min.1), max.map(|max| &max.1)),
+        monitor.height,
+        dpi,
+    );
+    let offset_x = layout.position.0.resolve(monitor.width, dpi);
+    let offset_y = layout.position.1.resolve(monitor.height, dpi);
+
+    Rect {
+        x: align(
+            layout.anchor.horizontal(),
+            monitor.x,
+            monitor.width,
+            width,
+            offset_x,
+        ),
+        y: align(
+            layout.anchor.vertical(),
+            monitor.y,
+            monitor.height,
+            height,
+            offset_y,
+        ),
+        width,
+        height,
+    }
+}
+
+/// Returns the layout that resolves to `rect` on the given monitor, keeping the anchor and the units
+/// of `layout`. This is the inverse of [`resolve`].
+pub fn fit(layout: &Layout, rect: &Rect, monitor: &Rect, dpi: u32) -> Layout {
+    let offset_x = offset(
+        layout.anchor.horizontal(),
+        monitor.x,
+        monitor.width,
+        rect.width,
+        rect.x,
+    );
+    let offset_y = offset(
+        layout.anchor.vertical(),
+        monitor.y,
+        monitor.height,
+        rect.height,
+        rect.y,
+    );
+
+    Layout {
+        position: (
+            layout.position.0.with_pixels(offset_x, monitor.width, dpi),
+            layout.position.1.with_pixels(offset_y, monitor.height, dpi),
+        ),
+        dimension: (
+            layout
+                .dimension
+                .0
+                .with_pixels(rect.width, monitor.width, dpi),
+            layout
+                .dimension
+                .1
+                .with_pixels(rect.height, monitor.height, dpi),
+        ),
+        ..layout.clone()
+    }
+}
+
+/// Deserializes the `dim` option, where `auto` on its own sizes both dimensions to the content.
+pub fn deserialize_dimension<'de, D>(deserializer: D) -> Result<Option<(Length, Length)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct DimensionVisitor;
+
+    impl<'de> de::Visitor<'de> for DimensionVisitor {
+        type Value = (Length, Length);
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a width and a height, or auto")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            match v.trim() {
+                "auto" => Ok((Length::Auto, Length::Auto)),
+                _ => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Deserialize::deserialize(de::value::SeqAccessDeserializer::new(seq))
+        }
+    }
+
+    struct OptionVisitor;
+
+    impl<'de> de::Visitor<'de> for OptionVisitor {
+        type Value = Option<(Length, Length)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a width and a height, or auto")
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(
+            self,
+            deserializer: D,
+        ) -> Result<Self::Value, D::Error> {
+            deserializer.deserialize_any(DimensionVisitor).map(Some)
+        }
+    }
+
+    deserializer.deserialize_option(OptionVisitor)
+}
+
+/// Deserializes the `pos` option, where lengths can't be `auto`.
+pub fn deserialize_position<'de, D>(deserializer: D) -> Result<Option<(Length, Length)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let position = Option::<(Length, Length)>::deserialize(deserializer)?;
+    if let Some((x, y)) = position {
+        if x == Length::Auto || y == Length::Auto {
+            return Err(de::Error::custom("positions can't be auto"));
+        }
+    }
+    Ok(position)
+}
+
+/// The schema of the `pos` option, which is a pair of lengths that can't be `"auto"`.
+pub fn position_schema(gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<Option<(Offset, Offset)>>()
+}
+
+/// The schema of the `dim` option, which is either a pair of lengths or `"auto"`.
+pub fn dimension_schema(gen: &mut SchemaGenerator) -> Schema {
+    let auto = SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        enum_values: Some(vec!["auto".into()]),
+        ..Default::default()
+    };
+
+    SchemaObject {
+        subschemas: Some(Box::new(SubschemaValidation {
+            any_of: Some(vec![gen.subschema_for::<(Length, Length)>(), auto.into()]),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Positions a span of `size` within the monitor span starting at `start` with length `extent`.
+fn align(alignment: Alignment, start: i32, extent: i32, size: i32, offset: i32) -> i32 {
+    match alignment {
+        Alignment::Start => start + offset,
+        Alignment::Center => start + (extent - size) / 2 + offset,
+        Alignment::End => start + extent - size - offset,
+    }
+}
+
+/// Returns the offset that [`align`] positions a span of `size` at `position` with.
+fn offset(alignment: Alignment, start: i32, extent: i32, size: i32, position: i32) -> i32 {
+    match alignment {
+        Alignment::Start => position - start,
+        Alignment::Center => position - start - (extent - size) / 2,
+        Alignment::End => start + extent - size - position,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use crate::dpi::DEFAULT_DPI;
+
+    use super::{fit, length_pattern, resolve, Anchor, Layout, Length, Rect};
+
+    const PRIMARY: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+
+    /// A secondary monitor placed to the left of the primary monitor.
+    const SECONDARY: Rect = Rect {
+        x: -1280,
+        y: 200,
+        width: 1280,
+        height: 720,
+    };
+
+    fn layout(anchor: Anchor, position: (Length, Length), dimension: (Length, Length)) -> Layout {
+        Layout {
+            anchor,
+            position,
+            dimension,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_matches_absolute_position() {
+        let layout = layout(
+            Anchor::TopLeft,
+            (Length::Pixels(100), Length::Pixels(50)),
+            (Length::Pixels(200), Length::Pixels(20)),
+        );
+
+        assert_eq!(
+            resolve(&layout, &PRIMARY, DEFAULT_DPI),
+            Rect {
+                x: 100,
+                y: 50,
+                width: 200,
+                height: 20
+            }
+        );
+    }
+
+    #[test]
+    fn test_anchor_top_right() {
+        let layout = layout(
+            Anchor::TopRight,
+            (Length::Pixels(10), Length::Pixels(10)),
+            (Length::Pixels(200), Length::Pixels(20)),
+        );
+
+        assert_eq!(
+            resolve(&layout, &PRIMARY, DEFAULT_DPI),
+            Rect {
+                x: 1710,
+                y: 10,
+                width: 200,
+                height: 20
+            }
+        );
+        assert_eq!(
+            resolve(&layout, &SECONDARY, DEFAULT_DPI),
+            Rect {
+                x: -210,
+                y: 210,
+                width: 200,
+                height: 20
+            }
+        );
+    }
+
+    #[test]
+    fn test_anchor_center_and_bottom() {
+        let centered = layout(
+            Anchor::Center,
+            (Length::Pixels(0), Length::Pixels(0)),
+            (Length::Pixels(200), Length::Pixels(100)),
+        );
+        assert_eq!(
+            resolve(&centered, &PRIMARY, DEFAULT_DPI),
+            Rect {
+                x: 860,
+                y: 490,
+                width: 200,
+                height: 100
+            }
+        );
+
+        let bottom = layout(
+            Anchor::Bottom,
+            (Length::Pixels(0), Length::Pixels(40)),
+            (Length::Percent(100.0), Length::Pixels(30)),
+        );
+        assert_eq!(
+            resolve(&bottom, &SECONDARY, DEFAULT_DPI),
+            Rect {
+                x: -1280,
+                y: 850,
+                width: 1280,
+                height: 30
+            }
+        );
+    }
+
+    #[test]
+    fn test_percentages() {
+        let layout = layout(
+            Anchor::TopLeft,
+            (Length::Percent(50.0), Length::Percent(10.0)),
+            (Length::Percent(25.0), Length::Percent(5.0)),
+        );
+
+        assert_eq!(
+            resolve(&layout, &SECONDARY, DEFAULT_DPI),
+            Rect {
+                x: -640,
+                y: 272,
+                width: 320,
+                height: 36
+            }
+        );
+    }
+
+    #[test]
+    fn test_dips() {
+        let layout = layout(
+            Anchor::TopRight,
+            (Length::Dip(10.0), Length::Dip(10.0)),
+            (Length::Dip(200.0), Length::Dip(20.0)),
+        );
+
+        assert_eq!(
+            resolve(&layout, &PRIMARY, 192),
+            Rect {
+                x: 1500,
+                y: 20,
+                width: 400,
+                height: 40
+            }
+        );
+        assert_eq!(
+            resolve(&layout, &PRIMARY, 144),
+            Rect {
+                x: 1605,
+                y: 15,
+                width: 300,
+                height: 30
+            }
+        );
+    }
+
+    #[test]
+    fn test_fit_round_trip() {
+        let rect = Rect {
+            x: 1500,
+            y: 900,
+            width: 300,
+            height: 50,
+        };
+
+        for anchor in [Anchor::TopLeft, Anchor::Center, Anchor::BottomRight] {
+            let original = layout(
+                anchor,
+                (Length::Percent(10.0), Length::Dip(20.0)),
+                (Length::Pixels(200), Length::Percent(5.0)),
+            );
+
+            for dpi in [DEFAULT_DPI, 144] {
+                let fitted = fit(&original, &rect, &PRIMARY, dpi);
+                assert_eq!(
+                    resolve(&fitted, &PRIMARY, dpi),
+                    rect,
+                    "{anchor:?} at {dpi} DPI"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_keeps_units() {
+        let original = layout(
+            Anchor::TopRight,
+            (Length::Pixels(10), Length::Percent(10.0)),
+            (Length::Dip(200.0), Length::Dip(20.0)),
+        );
+        let rect = resolve(&original, &SECONDARY, 144);
+
+        // nothing moved, so nothing changes
+        assert_eq!(fit(&original, &rect, &SECONDARY, 144), original);
+
+        // grown by 150 pixels to the left, and moved 100 pixels to the left
+        let moved = Rect {
+            x: rect.x - 250,
+            y: rect.y + 36,
+            width: 450,
+            ..rect
+        };
+        assert_eq!(
+            fit(&original, &moved, &SECONDARY, 144),
+            layout(
+                Anchor::TopRight,
+                (Length::Pixels(110), Length::Percent(15.0)),
+                (Length::Dip(300.0), Length::Dip(20.0)),
+            )
+        );
+    }
+
+    #[test]
+    fn test_auto_dimensions() {
+        let mut auto = layout(
+            Anchor::TopRight,
+            (Length::Pixels(10), Length::Pixels(10)),
+            (Length::Auto, Length::Pixels(20)),
+        );
+
+        // before the content is measured
+        assert_eq!(resolve(&auto, &PRIMARY, DEFAULT_DPI).width, 100);
+
+        // a right-anchored widget grows to the left
+        auto.content_size = Some((300.0, 50.0));
+        assert_eq!(
+            resolve(&auto, &PRIMARY, DEFAULT_DPI),
+            Rect {
+                x: 1610,
+                y: 10,
+                width: 300,
+                height: 20
+            }
+        );
+        auto.content_size = Some((400.0, 50.0));
+        assert_eq!(resolve(&auto, &PRIMARY, DEFAULT_DPI).x, 1510);
+
+        // the content size follows the DPI of the monitor
+        assert_eq!(resolve(&auto, &PRIMARY, 192).width, 800);
+
+        // auto dimensions stay auto when fitting the layout to a rect
+        let rect = resolve(&auto, &PRIMARY, DEFAULT_DPI);
+        let moved = Rect { y: 20, ..rect };
+        assert_eq!(
+            fit(&auto, &moved, &PRIMARY, DEFAULT_DPI).dimension,
+            (Length::Auto, Length::Pixels(20))
+        );
+    }
+
+    #[test]
+    fn test_auto_dimension_limits() {
+        let mut auto = layout(
+            Anchor::Center,
+            (Length::Pixels(0), Length::Pixels(0)),
+            (Length::Auto, Length::Auto),
+        );
+        auto.min_dimension = Some((Length::Pixels(100), Length::Dip(30.0)));
+        auto.max_dimension = Some((Length::Percent(25.0), Length::Auto));
+
+        auto.content_size = Some((25.0, 10.0));
+        let rect = resolve(&auto, &PRIMARY, 192);
+        assert_eq!((rect.width, rect.height), (100, 60));
+
+        // auto limits are ignored
+        auto.content_size = Some((500.0, 500.0));
+        let rect = resolve(&auto, &PRIMARY, 192);
+        assert_eq!((rect.width, rect.height), (480, 1000));
+        assert_eq!((rect.x, rect.y), (720, 40));
+    }
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!("200".parse::<Length>(), Ok(Length::Pixels(200)));
+        assert_eq!("-20px".parse::<Length>(), Ok(Length::Pixels(-20)));
+        assert_eq!(" 12.5% ".parse::<Length>(), Ok(Length::Percent(12.5)));
+        assert_eq!("200dip".parse::<Length>(), Ok(Length::Dip(200.0)));
+        assert_eq!("1.5 dip".parse::<Length>(), Ok(Length::Dip(1.5)));
+        assert_eq!("auto".parse::<Length>(), Ok(Length::Auto));
+        assert!("abc".parse::<Length>().is_err());
+        assert!("%".parse::<Length>().is_err());
+        assert!("dip".parse::<Length>().is_err());
+        assert!("1.5px".parse::<Length>().is_err());
+    }
+
+    #[test]
+    fn test_length_pattern_matches_parser() {
+        let pattern = regex::Regex::new(&length_pattern(true)).unwrap();
+        let offset_pattern = regex::Regex::new(&length_pattern(false)).unwrap();
+        for s in [
+            "200", "-20px", "+20 px", " 12.5% ", "200dip", "1.5 dip", ".5dip", "5.dip", "1e2%",
+            "-2.5E-1%", "auto", " auto ", "abc", "%", "dip", "px", "1.5px", "1.5", "1.5.2%", "1e%",
+            "inf%", "NaN dip", "50%%", "autos", "--5",
+        ] {
+            let length = s.parse::<Length>();
+            assert_eq!(pattern.is_match(s), length.is_ok(), "{s:?}");
+            assert_eq!(
+                offset_pattern.is_match(s),
+                length.is_ok_and(|length| length != Length::Auto),
+                "{s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize_length() {
+        let lengths: (Length, Length) = serde_json::from_str(r#"[10, "50%"]"#).unwrap();
+        assert_eq!(lengths, (Length::Pixels(10), Length::Percent(50.0)));
+
+        let lengths: (Length, Length) = serde_lexpr::from_str(r#"#(-5 "100%")"#).unwrap();
+        assert_eq!(lengths, (Length::Pixels(-5), Length::Percent(100.0)));
+
+        let lengths: (Length, Length) = serde_lexpr::from_str(r#"#("200dip" "20dip")"#).unwrap();
+        assert_eq!(lengths, (Length::Dip(200.0), Length::Dip(20.0)));
+    }
+
+    #[test]
+    fn test_deserialize_dimension() {
+        #[derive(Debug, Deserialize)]
+        struct Options {
+            #[serde(default, deserialize_with = "super::deserialize_dimension")]
+            dim: Option<(Length, Length)>,
+        }
+
+        let parse = |s: &str| serde_lexpr::from_str::<Options>(s).map(|options| options.dim);
+        assert_eq!(parse("()").unwrap(), None);
+        assert_eq!(
+            parse(r#"((dim "auto"))"#).unwrap(),
+            Some((Length::Auto, Length::Auto))
+        );
+        assert_eq!(
+            parse(r#"((dim #("auto" 20)))"#).unwrap(),
+            Some((Length::Auto, Length::Pixels(20)))
+        );
+        assert!(parse(r#"((dim "fill"))"#).is_err());
+
+        let options: Options = serde_json::from_str(r#"{"dim": [200, "auto"]}"#).unwrap();
+        assert_eq!(options.dim, Some((Length::Pixels(200), Length::Auto)));
+        let options: Options = serde_json::from_str(r#"{"dim": "auto"}"#).unwrap();
+        assert_eq!(options.dim, Some((Length::Auto, Length::Auto)));
+    }
+}
@@ -1,20 +1,33 @@
 use std::{
+    fs,
+    path::PathBuf,
     sync::{
-        atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering},
+        atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
         Arc, Mutex,
     },
     thread,
+    time::Duration,
 };
 
+use animation::{Hover, Transition};
 use anyhow::{anyhow, Result};
+use binding::Registry;
 use clap::Parser;
-use cli::{FloraCli, FloraSubcommand};
+use cli::{ConfigSubcommand, FloraCli, FloraSubcommand, StorageSubcommand};
+use click_through::ClickThrough;
+use color::Color;
+use hotkey::Hotkey;
+use layer::Layer;
+use layout::{Layout, Rect};
 use lazy_static::lazy_static;
+use monitor::{Monitor, MonitorTarget};
+use params::{ParamSpec, Params};
 use pipe::protocol::{ServerRequest, ServerResponse};
 use process::get_all_flora_processes;
+use snap::Snap;
 use tabled::{builder::Builder, settings::Style};
 use tokio::runtime;
-use tracing::info;
+use tracing::{info, warn};
 use window::{FloraHandle, FloraSender, FloraWindow};
 use windows::Win32::{
     Foundation::BOOL,
@@ -28,42 +41,198 @@ use windows::Win32::{
     },
 };
 
+mod animation;
+mod api;
+mod auto_size;
+mod binding;
 mod cli;
+mod click_through;
 mod color;
 mod config;
+mod config_edit;
+mod dpi;
+mod edit_mode;
+mod fullscreen;
+mod hotkey;
+mod layer;
+mod layout;
+mod monitor;
+mod params;
 mod pipe;
 mod process;
+mod rpc;
+mod settings;
+mod snap;
+mod storage;
+mod types;
 mod window;
 mod windows_api;
 
-static POS_X: AtomicI32 = AtomicI32::new(0);
-static POS_Y: AtomicI32 = AtomicI32::new(0);
-static WIDTH: AtomicI32 = AtomicI32::new(200);
-static HEIGHT: AtomicI32 = AtomicI32::new(20);
 static CONTENT_URL: AtomicBool = AtomicBool::new(false);
 
 static WINDOW_THREAD_ID: AtomicU32 = AtomicU32::new(0);
 
+/// The alpha value applied to the whole widget window.
+static OPACITY: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Whether the widget hides itself while an application is fullscreen on its monitor.
+static HIDE_ON_FULLSCREEN: AtomicBool = AtomicBool::new(false);
+
+/// How long transitions take, in milliseconds.
+static TRANSITION_DURATION: AtomicU32 = AtomicU32::new(animation::DEFAULT_TRANSITION_DURATION);
+
+/// How long calls from the page wait for a result before they are rejected, in milliseconds.
+static RPC_TIMEOUT: AtomicU32 = AtomicU32::new(binding::DEFAULT_RPC_TIMEOUT);
+
 lazy_static! {
     static ref NAME: Arc<Mutex<String>> = Arc::new(Mutex::new("Generic Flora Widget".to_string()));
+    static ref LAYOUT: Arc<Mutex<Layout>> = Arc::new(Mutex::new(Layout::default()));
+    static ref MONITOR: Arc<Mutex<MonitorTarget>> = Arc::new(Mutex::new(MonitorTarget::default()));
+    static ref LAYER: Arc<Mutex<Layer>> = Arc::new(Mutex::new(Layer::default()));
+    static ref BACKGROUND: Arc<Mutex<Option<Color>>> = Arc::new(Mutex::new(None));
+    static ref CLICK_THROUGH: Arc<Mutex<ClickThrough>> =
+        Arc::new(Mutex::new(ClickThrough::default()));
+    static ref SNAP: Arc<Mutex<Snap>> = Arc::new(Mutex::new(Snap::default()));
+    static ref TRANSITION: Arc<Mutex<Transition>> = Arc::new(Mutex::new(Transition::default()));
+    static ref HOVER: Arc<Mutex<Hover>> = Arc::new(Mutex::new(Hover::default()));
+    static ref HOTKEYS: Arc<Mutex<Vec<Hotkey>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref PARAMS: Arc<Mutex<Params>> = Arc::new(Mutex::new(Params::new()));
+    static ref PARAM_SCHEMA: Arc<Mutex<Vec<ParamSpec>>> = Arc::new(Mutex::new(Vec::new()));
+    /// The config the widget was started with, which edit mode writes the widget bounds back into.
+    static ref CONFIG_PATH: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
     static ref CONTENT: Arc<Mutex<String>> =
         Arc::new(Mutex::new(include_str!("../default.html").to_string()));
     static ref SENDER: Arc<Mutex<Option<FloraSender>>> = Arc::new(Mutex::new(None));
     static ref HANDLE: Arc<Mutex<FloraHandle>> = Arc::new(Mutex::new(FloraHandle::default()));
 }
 
+/// Returns the monitor the widget is placed on, or `None` if no monitors are found.
+pub fn find_target_monitor() -> Option<Monitor> {
+    let monitors = windows_api::get_monitors();
+    let target = MONITOR.lock().unwrap().clone();
+
+    let (monitor, fallback) = monitor::select(&monitors, &target)?;
+    if fallback {
+        warn!(
+            ?target,
+            monitor = monitor.name,
+            "target monitor not found, falling back"
+        );
+    }
+    Some(monitor.clone())
+}
+
+/// Returns the bounds and DPI of the monitor the widget is placed on.
+pub fn get_target_monitor() -> (Rect, u32) {
+    match find_target_monitor() {
+        Some(monitor) => (monitor.bounds, monitor.dpi),
+        None => {
+            warn!("no monitors found, using the virtual screen origin");
+            (Rect::default(), dpi::DEFAULT_DPI)
+        }
+    }
+}
+
+/// Resolves the rect the widget window should occupy, based on the configured layout and the
+/// currently connected monitors.
+pub fn get_window_rect() -> Rect {
+    let (monitor, dpi) = get_target_monitor();
+
+    let layout = LAYOUT.lock().unwrap();
+    layout::resolve(&layout, &monitor, dpi)
+}
+
+/// Records the measured size of the page, in CSS pixels, returning the rect the widget window
+/// should now occupy.
+pub fn set_content_size(width: f64, height: f64, device_pixel_ratio: f64) -> Rect {
+    let (_, dpi) = get_target_monitor();
+    LAYOUT.lock().unwrap().content_size =
+        Some(auto_size::to_dips(width, height, device_pixel_ratio, dpi));
+    get_window_rect()
+}
+
+/// Updates the layout so the widget window occupies `rect`, returning the layout before and after.
+fn fit_layout(rect: &Rect) -> (Layout, Layout) {
+    let (monitor, dpi) = get_target_monitor();
+    let mut layout = LAYOUT.lock().unwrap();
+    let old = layout.clone();
+    *layout = layout::fit(&layout, rect, &monitor, dpi);
+    (old, layout.clone())
+}
+
+/// Updates the layout so the widget window occupies `rect` for as long as the widget runs,
+/// returning the rect the window should now occupy. This differs from `rect` where the widget is
+/// sized to its content.
+pub fn place_window(rect: &Rect) -> Rect {
+    fit_layout(rect);
+    get_window_rect()
+}
+
+/// Updates the layout so the widget window occupies `rect`, and writes the changed position and
+/// dimension back into the widget's config.
+pub fn save_window_rect(rect: &Rect) -> Result<()> {
+    let (old, new) = fit_layout(rect);
+
+    let mut options = Vec::new();
+    if new.position != old.position {
+        options.push(("pos", new.position));
+    }
+    if new.dimension != old.dimension {
+        options.push(("dim", new.dimension));
+    }
+
+    let path = CONFIG_PATH.lock().unwrap().clone();
+    if let Some(path) = path.filter(|_| !options.is_empty()) {
+        config_edit::set_options(&path, &options)?;
+        info!(path = %path.display(), "saved widget bounds to config");
+    }
+
+    Ok(())
+}
+
 fn create_window() -> Result<FloraWindow> {
-    let x = POS_X.load(Ordering::SeqCst);
-    let y = POS_Y.load(Ordering::SeqCst);
-    let width = WIDTH.load(Ordering::SeqCst);
-    let height = HEIGHT.load(Ordering::SeqCst);
+    let rect = get_window_rect();
     let content = {
         let c = CONTENT.lock().unwrap();
         c.clone()
     };
     let content_url = CONTENT_URL.load(Ordering::SeqCst);
-
-    let window = FloraWindow::new(x, y, width, height, false)?;
+    let layer = *LAYER.lock().unwrap();
+    let background = BACKGROUND.lock().unwrap().clone();
+    let opacity = OPACITY.load(Ordering::SeqCst);
+    let click_through = *CLICK_THROUGH.lock().unwrap();
+    let snap = *SNAP.lock().unwrap();
+    let auto_size = LAYOUT.lock().unwrap().auto_size();
+    let transition = *TRANSITION.lock().unwrap();
+    let transition_duration = TRANSITION_DURATION.load(Ordering::SeqCst);
+    let hover_opacity = HOVER.lock().unwrap().opacity;
+    let hide_on_fullscreen = HIDE_ON_FULLSCREEN.load(Ordering::SeqCst);
+    let hotkeys = HOTKEYS.lock().unwrap().clone();
+    let rpc_timeout = RPC_TIMEOUT.load(Ordering::SeqCst);
+    let params = PARAMS.lock().unwrap().clone();
+
+    let window = FloraWindow::new(
+        rect.x,
+        rect.y,
+        rect.width,
+        rect.height,
+        background.as_ref(),
+        false,
+    )?;
+    window.set_layer(layer)?;
+    window.set_opacity(opacity)?;
+    window.set_click_through(click_through)?;
+    window.set_snap(snap);
+    window.set_auto_size(auto_size)?;
+    window.set_transition(
+        transition,
+        Duration::from_millis(transition_duration as u64),
+    );
+    window.set_hover_opacity(hover_opacity)?;
+    window.set_hide_on_fullscreen(hide_on_fullscreen)?;
+    window.set_hotkeys(&hotkeys);
+    window.set_rpc_timeout(rpc_timeout)?;
+    window.set_params(&params)?;
     if content_url {
         window.navigate(&content);
     } else {
@@ -73,6 +242,67 @@ fn create_window() -> Result<FloraWindow> {
     Ok(window)
 }
 
+/// How often the config is checked for changes while the widget runs.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Watches the config the widget was started with, along with the configs it extends and the
+/// user-wide defaults file, passing its params on to the page whenever they change. Other options
+/// apply the next time the widget starts.
+fn watch_config() {
+    let Some(path) = CONFIG_PATH.lock().unwrap().clone() else {
+        return;
+    };
+
+    thread::spawn(move || {
+        let modified_at = |files: &[PathBuf]| {
+            files
+                .iter()
+                .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+                .collect::<Vec<_>>()
+        };
+        let mut files = config::source_files(&path);
+        let mut last_modified = modified_at(&files);
+        loop {
+            thread::sleep(CONFIG_POLL_INTERVAL);
+            if modified_at(&files) == last_modified {
+                continue;
+            }
+            // the change may have moved the `extends` chain to other files
+            files = config::source_files(&path);
+            last_modified = modified_at(&files);
+
+            // edit mode writes the bounds of the widget into the config, leaving the params as is,
+            // while the settings page has already applied the params it writes
+            let config = match config::resolve(&path) {
+                Ok(config) => config,
+                Err(e) => {
+                    warn!("could not reload config: {e:#}");
+                    continue;
+                }
+            };
+            *PARAM_SCHEMA.lock().unwrap() = config.param_schema();
+            let params = config.params();
+            {
+                let mut p = PARAMS.lock().unwrap();
+                if *p == params {
+                    continue;
+                }
+                *p = params.clone();
+            }
+
+            info!("reloading params");
+            let result = execute(move |window| {
+                if let Err(e) = window.set_params(&params) {
+                    warn!("could not update params: {e:#}");
+                }
+            });
+            if let Err(e) = result {
+                warn!("could not update params: {e:#}");
+            }
+        }
+    });
+}
+
 fn start_named_pipe_server() {
     thread::spawn(move || {
         let rt = runtime::Runtime::new().unwrap();
@@ -92,7 +322,6 @@ fn start() -> Result<()> {
     unsafe {
         CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok()?;
     }
-    set_process_dpi_awareness()?;
 
     let window = create_window()?;
     {
@@ -106,6 +335,7 @@ fn start() -> Result<()> {
     WINDOW_THREAD_ID.store(window.thread_id, Ordering::SeqCst);
 
     start_named_pipe_server();
+    watch_config();
 
     window
         .run()
@@ -114,27 +344,27 @@ fn start() -> Result<()> {
 
 fn main() -> Result<()> {
     let cli = FloraCli::parse();
+    // this also applies to subcommands other than start, so that window bounds are reported in
+    // physical pixels
+    set_process_dpi_awareness()?;
+
     match cli.command {
         FloraSubcommand::Start { config_path } => {
             if !config_path.is_file() {
                 println!("Specified config path is not a valid file");
                 return Ok(());
             }
-            match config_path.extension() {
-                Some(ext) => {
-                    if ext != "flora" {
-                        println!("Config extension must be .flora");
-                        return Ok(());
-                    }
-                }
-                None => {
-                    println!("Config extension must be .flora");
-                    return Ok(());
-                }
+            if let Err(e) = config::ConfigFormat::from_path(&config_path) {
+                println!("Invalid config path: {e}");
+                return Ok(());
             }
 
-            let config = config::read(&config_path)?;
+            let config = config::resolve(&config_path)?;
             config::load_config(config);
+            {
+                let mut path = CONFIG_PATH.lock().unwrap();
+                *path = Some(config_path);
+            }
 
             return start();
         }
@@ -156,6 +386,8 @@ fn main() -> Result<()> {
                         process.y.to_string(),
                         process.width.to_string(),
                         process.height.to_string(),
+                        process.dpi.to_string(),
+                        format!("{}%", (dpi::scale_factor(process.dpi) * 100.0).round()),
                     ]
                 })
                 .collect();
@@ -169,6 +401,8 @@ fn main() -> Result<()> {
                     "y".to_string(),
                     "width".to_string(),
                     "height".to_string(),
+                    "dpi".to_string(),
+                    "scale".to_string(),
                 ],
             );
 
@@ -180,70 +414,136 @@ fn main() -> Result<()> {
             return Ok(());
         }
         FloraSubcommand::Show { all, name } => {
-            if !all && name.is_none() {
-                println!("Please specify the --all flag or the name of a widget");
-                return Ok(());
-            }
+            send_to_widgets(all, name, ServerRequest::ShowWindow, "show")
+        }
+        FloraSubcommand::Hide { all, name } => {
+            send_to_widgets(all, name, ServerRequest::HideWindow, "hide")
+        }
+        FloraSubcommand::Toggle { all, name } => {
+            send_to_widgets(all, name, ServerRequest::ToggleWindow, "toggle")
+        }
+        FloraSubcommand::Reload { all, name } => {
+            send_to_widgets(all, name, ServerRequest::Reload, "reload")
+        }
+        FloraSubcommand::Layer { layer, all, name } => {
+            send_to_widgets(all, name, ServerRequest::SetLayer(layer), "move")
+        }
+        FloraSubcommand::ClickThrough { mode, all, name } => {
+            send_to_widgets(all, name, ServerRequest::SetClickThrough(mode), "update")
+        }
+        FloraSubcommand::Edit { name } => {
+            send_to_widgets(false, Some(name), ServerRequest::ToggleEditMode, "edit")
+        }
+        FloraSubcommand::Settings { name } => send_to_widgets(
+            false,
+            Some(name),
+            ServerRequest::OpenSettings,
+            "open settings of",
+        ),
+        FloraSubcommand::Config { command } => match command {
+            ConfigSubcommand::Show { resolved, path } => {
+                if !path.is_file() {
+                    println!("Specified config path is not a valid file");
+                    return Ok(());
+                }
 
-            let processes = get_all_flora_processes();
+                let config = match resolved {
+                    true => config::resolve(&path)?,
+                    false => config::read(&path)?,
+                };
+                let format = config::ConfigFormat::from_path(&path)?;
+                println!("{}", config::to_string_as(format, &config)?);
 
-            if all {
-                for process in processes {
-                    let res = process.send(ServerRequest::ShowWindow)?;
-                    if let ServerResponse::Err(e) = res {
-                        println!("Could not show widget {}: {e}\nContinuing...", process.name);
-                    }
+                Ok(())
+            }
+            ConfigSubcommand::Convert { input, output } => {
+                if !input.is_file() {
+                    println!("Specified config path is not a valid file");
+                    return Ok(());
+                }
+                if let Err(e) = config::ConfigFormat::from_path(&output) {
+                    println!("Invalid output path: {e}");
+                    return Ok(());
                 }
 
-                return Ok(());
-            }
+                config::write(&output, &config::read(&input)?)?;
 
-            let name = name.unwrap();
+                Ok(())
+            }
+            ConfigSubcommand::Schema => {
+                println!("{}", serde_json::to_string_pretty(&config::schema())?);
 
-            if let Some(target) = processes.iter().find(|process| process.name == name) {
-                let res = target.send(ServerRequest::ShowWindow)?;
-                if let ServerResponse::Err(e) = res {
-                    println!("Could not show widget {}: {e}", target.name);
+                Ok(())
+            }
+        },
+        FloraSubcommand::Storage { name, command } => {
+            let mut storage = storage::open(&name)?;
+            match command {
+                StorageSubcommand::Dump => {
+                    println!("{}", serde_json::to_string_pretty(storage.entries())?);
+                }
+                StorageSubcommand::Clear => {
+                    storage.clear()?;
+                    println!(
+                        "Cleared the storage of {name} ({})",
+                        storage.path().display()
+                    );
                 }
-            } else {
-                println!("Could not find the specified widget name {}", name);
             }
 
-            return Ok(());
+            Ok(())
         }
-        FloraSubcommand::Hide { all, name } => {
-            if !all && name.is_none() {
-                println!("Please specify the --all flag or the name of a widget");
-                return Ok(());
-            }
+        FloraSubcommand::Types => {
+            let mut registry = Registry::default();
+            api::register(&mut registry);
+            print!("{}", types::declarations(&registry));
 
-            let processes = get_all_flora_processes();
+            Ok(())
+        }
+    }
+}
 
-            if all {
-                for process in processes {
-                    let res = process.send(ServerRequest::HideWindow)?;
-                    if let ServerResponse::Err(e) = res {
-                        println!("Could not hide widget {}: {e}\nContinuing...", process.name);
-                    }
-                }
+/// Sends the request to the widget with the given name, or all widgets if `all` is set. `action`
+/// describes the request in error messages.
+fn send_to_widgets(
+    all: bool,
+    name: Option<String>,
+    request: ServerRequest,
+    action: &str,
+) -> Result<()> {
+    if !all && name.is_none() {
+        println!("Please specify the --all flag or the name of a widget");
+        return Ok(());
+    }
 
-                return Ok(());
+    let processes = get_all_flora_processes();
+
+    if all {
+        for process in processes {
+            let res = process.send(request.clone())?;
+            if let ServerResponse::Err(e) = res {
+                println!(
+                    "Could not {action} widget {}: {e}\nContinuing...",
+                    process.name
+                );
             }
+        }
 
-            let name = name.unwrap();
+        return Ok(());
+    }
 
-            if let Some(target) = processes.iter().find(|process| process.name == name) {
-                let res = target.send(ServerRequest::HideWindow)?;
-                if let ServerResponse::Err(e) = res {
-                    println!("Could not hide widget {}: {e}", target.name);
-                }
-            } else {
-                println!("Could not find the specified widget name {}", name);
-            }
+    let name = name.unwrap();
 
-            return Ok(());
+    if let Some(target) = processes.iter().find(|process| process.name == name) {
+        let res = target.send(request)?;
+        if let ServerResponse::Err(e) = res {
+            println!("Could not {action} widget {}: {e}", target.name);
         }
+    } else {
+        println!("Could not find the specified widget name {}", name);
     }
+
+    Ok(())
 }
 
 fn set_process_dpi_awareness() -> Result<()> {
@@ -0,0 +1,264 @@
+//! Selects the monitor a widget is placed on.
+//!
+//! This is kept platform-neutral; the monitors themselves are enumerated by
+//! [`crate::windows_api::get_monitors`].
+use std::fmt;
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject, SubschemaValidation},
+    JsonSchema,
+};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::layout::Rect;
+
+/// A connected monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    /// The device name of the monitor, e.g. `\\.\DISPLAY1`.
+    pub name: String,
+    /// The bounds of the monitor in virtual screen coordinates.
+    pub bounds: Rect,
+    /// Whether this is the primary monitor.
+    pub primary: bool,
+    /// The effective DPI of the monitor.
+    pub dpi: u32,
+}
+
+/// The monitor a widget should be placed on.
+///
+/// In configs, this is `primary`, an index such as `1`, or a device name such as `"\\.\DISPLAY2"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MonitorTarget {
+    /// The primary monitor.
+    #[default]
+    Primary,
+    /// The monitor at the given index, where monitors are ordered from left to right (and top to
+    /// bottom for monitors sharing the same left edge), starting at 0.
+    Index(usize),
+    /// The monitor with the given device name, e.g. `\\.\DISPLAY2`.
+    Name(String),
+}
+
+impl Serialize for MonitorTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Primary => serializer.serialize_unit_variant("MonitorTarget", 0, "primary"),
+            Self::Index(index) => serializer.serialize_u64(*index as u64),
+            Self::Name(name) => serializer.serialize_str(name),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for MonitorTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MonitorTargetVisitor;
+
+        impl de::Visitor<'_> for MonitorTargetVisitor {
+            type Value = MonitorTarget;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "primary, a monitor index or a device name")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<MonitorTarget, E> {
+                usize::try_from(v)
+                    .map(MonitorTarget::Index)
+                    .map_err(|_| E::custom(format!("invalid monitor index {v}")))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<MonitorTarget, E> {
+                usize::try_from(v)
+                    .map(MonitorTarget::Index)
+                    .map_err(|_| E::custom(format!("invalid monitor index {v}")))
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<MonitorTarget, E> {
+                Ok(match v {
+                    "primary" => MonitorTarget::Primary,
+                    _ => MonitorTarget::Name(v.to_string()),
+                })
+            }
+        }
+
+        deserializer.deserialize_any(MonitorTargetVisitor)
+    }
+}
+
+impl JsonSchema for MonitorTarget {
+    fn schema_name() -> String {
+        "MonitorTarget".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let primary = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(vec!["primary".into()]),
+            ..Default::default()
+        };
+
+        SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                description: Some(
+                    "\"primary\", the index of a monitor ordered from left to right, or the device \
+                     name of a monitor such as \"\\\\.\\DISPLAY2\""
+                        .to_string(),
+                ),
+                ..Default::default()
+            })),
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![
+                    primary.into(),
+                    gen.subschema_for::<usize>(),
+                    gen.subschema_for::<String>(),
+                ]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+/// Orders the monitors from left to right, then top to bottom. Indices in [`MonitorTarget::Index`]
+/// refer to this order.
+pub fn sort(monitors: &mut [Monitor]) {
+    monitors.sort_by_key(|monitor| (monitor.bounds.x, monitor.bounds.y));
+}
+
+/// Finds the monitor matching the target. The monitors must already be ordered with [`sort`].
+pub fn find<'a>(monitors: &'a [Monitor], target: &MonitorTarget) -> Option<&'a Monitor> {
+    match target {
+        MonitorTarget::Primary => monitors.iter().find(|monitor| monitor.primary),
+        MonitorTarget::Index(index) => monitors.get(*index),
+        MonitorTarget::Name(name) => monitors
+            .iter()
+            .find(|monitor| monitor.name.eq_ignore_ascii_case(name)),
+    }
+}
+
+/// Selects the monitor matching the target, falling back to the primary monitor (or the first
+/// monitor if none are marked as primary) if the target is not connected.
+///
+/// Returns the selected monitor, and whether a fallback was used.
+pub fn select<'a>(monitors: &'a [Monitor], target: &MonitorTarget) -> Option<(&'a Monitor, bool)> {
+    if let Some(monitor) = find(monitors, target) {
+        return Some((monitor, false));
+    }
+
+    find(monitors, &MonitorTarget::Primary)
+        .or_else(|| monitors.first())
+        .map(|monitor| (monitor, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dpi::DEFAULT_DPI, layout::Rect};
+
+    use super::{select, sort, Monitor, MonitorTarget};
+
+    fn monitor(name: &str, x: i32, y: i32, primary: bool) -> Monitor {
+        Monitor {
+            name: name.to_string(),
+            bounds: Rect {
+                x,
+                y,
+                width: 1920,
+                height: 1080,
+            },
+            primary,
+            dpi: DEFAULT_DPI,
+        }
+    }
+
+    /// Three monitors side by side, with the primary in the middle.
+    fn monitors() -> Vec<Monitor> {
+        let mut monitors = vec![
+            monitor(r"\\.\DISPLAY1", 0, 0, true),
+            monitor(r"\\.\DISPLAY3", 1920, 0, false),
+            monitor(r"\\.\DISPLAY2", -1920, 0, false),
+        ];
+        sort(&mut monitors);
+        monitors
+    }
+
+    #[test]
+    fn test_sort() {
+        let names: Vec<_> = monitors().into_iter().map(|m| m.name).collect();
+
+        assert_eq!(names, [r"\\.\DISPLAY2", r"\\.\DISPLAY1", r"\\.\DISPLAY3"]);
+    }
+
+    #[test]
+    fn test_select() {
+        let monitors = monitors();
+
+        let (primary, fallback) = select(&monitors, &MonitorTarget::Primary).unwrap();
+        assert_eq!((primary.name.as_str(), fallback), (r"\\.\DISPLAY1", false));
+
+        let (first, fallback) = select(&monitors, &MonitorTarget::Index(0)).unwrap();
+        assert_eq!((first.name.as_str(), fallback), (r"\\.\DISPLAY2", false));
+
+        let target = MonitorTarget::Name(r"\\.\display3".to_string());
+        let (named, fallback) = select(&monitors, &target).unwrap();
+        assert_eq!((named.name.as_str(), fallback), (r"\\.\DISPLAY3", false));
+    }
+
+    #[test]
+    fn test_select_fallback() {
+        let monitors = monitors();
+
+        let (monitor, fallback) = select(&monitors, &MonitorTarget::Index(5)).unwrap();
+        assert_eq!((monitor.name.as_str(), fallback), (r"\\.\DISPLAY1", true));
+
+        let target = MonitorTarget::Name(r"\\.\DISPLAY9".to_string());
+        let (monitor, fallback) = select(&monitors, &target).unwrap();
+        assert_eq!((monitor.name.as_str(), fallback), (r"\\.\DISPLAY1", true));
+
+        // no monitor is marked as primary (e.g. while displays are being rearranged)
+        let unmarked: Vec<_> = monitors
+            .into_iter()
+            .map(|m| Monitor {
+                primary: false,
+                ..m
+            })
+            .collect();
+        let (monitor, fallback) = select(&unmarked, &MonitorTarget::Primary).unwrap();
+        assert_eq!((monitor.name.as_str(), fallback), (r"\\.\DISPLAY2", true));
+
+        assert!(select(&[], &MonitorTarget::Primary).is_none());
+    }
+
+    #[test]
+    fn test_parse_target() {
+        let target: MonitorTarget = serde_json::from_str(r#""primary""#).unwrap();
+        assert_eq!(target, MonitorTarget::Primary);
+
+        let target: MonitorTarget = serde_lexpr::from_str("1").unwrap();
+        assert_eq!(target, MonitorTarget::Index(1));
+
+        let target: MonitorTarget = serde_lexpr::from_str(r#""\\\\.\\DISPLAY2""#).unwrap();
+        assert_eq!(target, MonitorTarget::Name(r"\\.\DISPLAY2".to_string()));
+
+        let target: MonitorTarget = serde_json::from_str(r#""\\\\.\\DISPLAY2""#).unwrap();
+        assert_eq!(target, MonitorTarget::Name(r"\\.\DISPLAY2".to_string()));
+
+        let target: MonitorTarget = serde_json::from_str("0").unwrap();
+        assert_eq!(target, MonitorTarget::Index(0));
+
+        assert!(serde_json::from_str::<MonitorTarget>("-1").is_err());
+        assert!(serde_json::from_str::<MonitorTarget>(r#"{"index": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_print_target() {
+        let print = |target| serde_lexpr::to_string(&target).unwrap();
+        assert_eq!(print(MonitorTarget::Primary), "primary");
+        assert_eq!(print(MonitorTarget::Index(1)), "1");
+        assert_eq!(
+            print(MonitorTarget::Name(r"\\.\DISPLAY2".to_string())),
+            r#""\\\\.\\DISPLAY2""#
+        );
+    }
+}
@@ -0,0 +1,549 @@
+//! Free-form parameters for the page, given in the `params` option of the config and exposed to the
+//! page as `flora.params`, e.g. `(params (timezone "UTC") (threshold 80))`.
+//!
+//! Params are kept as JSON, which is what the page sees. TOML and JSON configs already hold them as
+//! tables and objects, while the flora format writes them as entries like other options:
+//!
+//! - `(name value)` holds a single value. Vectors and lists become arrays, symbols become strings,
+//!   and `#nil` becomes `null`.
+//! - `(name (key value) ...)` holds nested params, which become an object. A list whose first item
+//!   starts with a symbol or a string is read the same way, so arrays are best written as vectors.
+//! - `(name)` on its own is `true`, like flags.
+//!
+//! A widget can describe the params it takes with a [`ParamSpec`] for each in the `param-schema`
+//! option, e.g. `(param-schema (threshold (type integer) (default 80) (min 0) (max 100)))`. Params
+//! are then checked against the schema, and missing params get their defaults.
+use std::fmt;
+
+use anyhow::{anyhow, bail, Context, Result};
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Number, Value as Json};
+use serde_lexpr::Value;
+
+/// The params of a widget, by name.
+pub type Params = Map<String, Json>;
+
+/// A param a widget takes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ParamSpec {
+    /// The name of the param in `flora.params`
+    pub name: String,
+    /// The type of the values the param takes
+    #[serde(rename = "type")]
+    pub ty: ParamType,
+    /// The value of the param when the config doesn't give one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Json>,
+    /// The smallest value of a number param
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// The largest value of a number param
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// What the param does, shown on the settings page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// The type of the values a param takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::String => "a string",
+            Self::Number => "a number",
+            Self::Integer => "an integer",
+            Self::Boolean => "a boolean",
+        })
+    }
+}
+
+impl ParamSpec {
+    /// Checks that `value` has the type of the param, and is within its range.
+    pub fn check(&self, value: &Json) -> Result<()> {
+        let matches = match self.ty {
+            ParamType::String => value.is_string(),
+            ParamType::Number => value.is_number(),
+            ParamType::Integer => value.is_i64() || value.is_u64(),
+            ParamType::Boolean => value.is_boolean(),
+        };
+        if !matches {
+            bail!("expected {}, but got `{value}`", self.ty);
+        }
+
+        if let Some(number) = value.as_f64() {
+            if self.min.is_some_and(|min| number < min) || self.max.is_some_and(|max| number > max)
+            {
+                bail!("{value} is outside of {}", self.range());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describes the range of the param, e.g. `0 to 100`.
+    fn range(&self) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => format!("{min} to {max}"),
+            (Some(min), None) => format!("{min} and above"),
+            (None, Some(max)) => format!("{max} and below"),
+            (None, None) => "any number".to_string(),
+        }
+    }
+}
+
+/// Checks `params` against `schema`, returning them along with the defaults of the params they
+/// leave out. Params the schema doesn't know of are rejected, since they are likely misspelled.
+pub fn validate(schema: &[ParamSpec], params: &Params) -> Result<Params> {
+    for (i, spec) in schema.iter().enumerate() {
+        if schema[..i].iter().any(|other| other.name == spec.name) {
+            bail!("param `{}` is declared more than once", spec.name);
+        }
+        if (spec.min.is_some() || spec.max.is_some())
+            && !matches!(spec.ty, ParamType::Number | ParamType::Integer)
+        {
+            bail!("param `{}` has a range, but is not a number", spec.name);
+        }
+        if let (Some(min), Some(max)) = (spec.min, spec.max) {
+            if min > max {
+                bail!("param `{}` has a range from {min} to {max}", spec.name);
+            }
+        }
+        if let Some(default) = &spec.default {
+            spec.check(default)
+                .with_context(|| format!("invalid default for param `{}`", spec.name))?;
+        }
+    }
+
+    if let Some(name) = params
+        .keys()
+        .find(|name| !schema.iter().any(|spec| &spec.name == *name))
+    {
+        bail!("unknown param `{name}`");
+    }
+
+    let mut validated = params.clone();
+    for spec in schema {
+        match params.get(&spec.name) {
+            Some(value) => spec
+                .check(value)
+                .with_context(|| format!("invalid param `{}`", spec.name))?,
+            None => {
+                if let Some(default) = &spec.default {
+                    validated.insert(spec.name.clone(), default.clone());
+                }
+            }
+        }
+    }
+
+    Ok(validated)
+}
+
+/// Converts a param schema written in the flora format, where each param is an entry named after
+/// it, e.g. `(threshold (type integer) (default 80))`.
+pub fn schema_from_lexpr(entries: &Value) -> Result<Vec<ParamSpec>> {
+    let Some(entries) = entries.list_iter().filter(|_| entries.is_list()) else {
+        bail!("a param schema must be `(name (type type) ...)` entries, but got `{entries}`");
+    };
+
+    entries
+        .map(|entry| {
+            let (name, spec) = entry.as_pair().ok_or_else(|| {
+                anyhow!("expected a `(name (type type) ...)` entry, but got `{entry}`")
+            })?;
+            let name = name
+                .as_symbol()
+                .or_else(|| name.as_str())
+                .ok_or_else(|| anyhow!("param names must be symbols or strings, not `{name}`"))?;
+
+            let mut spec = from_lexpr(spec).with_context(|| format!("invalid param `{name}`"))?;
+            spec.insert("name".to_string(), Json::from(name));
+            serde_json::from_value(Json::Object(spec))
+                .with_context(|| format!("invalid param `{name}`"))
+        })
+        .collect()
+}
+
+/// Converts a param schema into entries in the flora format, which [`schema_from_lexpr`] reads
+/// back.
+pub fn schema_to_lexpr(schema: &[ParamSpec]) -> Value {
+    Value::list(schema.iter().map(|spec| {
+        let mut entries = match serde_json::to_value(spec) {
+            Ok(Json::Object(entries)) => entries,
+            _ => Params::new(),
+        };
+        entries.remove("name");
+        let name = match is_symbol(&spec.name) {
+            true => Value::symbol(spec.name.as_str()),
+            false => Value::string(spec.name.as_str()),
+        };
+        Value::cons(name, to_lexpr(&entries))
+    }))
+}
+
+/// Converts params written in the flora format, as the entries following `params`, into JSON.
+pub fn from_lexpr(entries: &Value) -> Result<Params> {
+    let Some(entries) = entries.list_iter().filter(|_| entries.is_list()) else {
+        bail!("params must be `(name value)` entries, but got `{entries}`");
+    };
+
+    entries
+        .map(|entry| {
+            let (key, value) = entry
+                .as_pair()
+                .ok_or_else(|| anyhow!("expected a `(name value)` entry, but got `{entry}`"))?;
+            let key = key
+                .as_symbol()
+                .or_else(|| key.as_str())
+                .ok_or_else(|| anyhow!("param names must be symbols or strings, not `{key}`"))?;
+            Ok((key.to_string(), entry_value(value)?))
+        })
+        .collect()
+}
+
+/// Converts params into entries in the flora format, which [`from_lexpr`] reads back. Objects are
+/// written as lists of entries, so empty objects are written as `()`, while arrays are written as
+/// vectors.
+pub fn to_lexpr(params: &Params) -> Value {
+    Value::list(params.iter().map(|(key, value)| {
+        let name = match is_symbol(key) {
+            true => Value::symbol(key.as_str()),
+            false => Value::string(key.as_str()),
+        };
+        match value {
+            Json::Object(object) if !object.is_empty() => Value::cons(name, to_lexpr(object)),
+            value => Value::list([name, from_json(value)]),
+        }
+    }))
+}
+
+/// Converts the value of an entry, which is everything following its name.
+fn entry_value(value: &Value) -> Result<Json> {
+    match value {
+        Value::Null => Ok(Json::Bool(true)),
+        Value::Cons(cons) if is_entry(cons.car()) => from_lexpr(value).map(Json::Object),
+        Value::Cons(cons) if cons.cdr().is_null() => to_json(cons.car()),
+        Value::Cons(_) => bail!("expected a single value, but got `{value}`"),
+        // `(name . value)`, as serde-lexpr writes maps
+        value => to_json(value),
+    }
+}
+
+fn to_json(value: &Value) -> Result<Json> {
+    Ok(match value {
+        Value::Nil => Json::Null,
+        // an empty list of entries, as empty objects are written
+        Value::Null => Json::Object(Params::new()),
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Number(n) => {
+            let number = n
+                .as_i64()
+                .map(Number::from)
+                .or_else(|| n.as_u64().map(Number::from))
+                .or_else(|| n.as_f64().and_then(Number::from_f64))
+                .ok_or_else(|| anyhow!("unsupported number `{n}`"))?;
+            Json::Number(number)
+        }
+        Value::Char(c) => Json::String(c.to_string()),
+        Value::String(s) | Value::Symbol(s) => Json::String(s.to_string()),
+        Value::Vector(values) => Json::Array(values.iter().map(to_json).collect::<Result<_>>()?),
+        Value::Cons(cons) if is_entry(cons.car()) => Json::Object(from_lexpr(value)?),
+        Value::Cons(_) if value.is_list() => Json::Array(
+            value
+                .list_iter()
+                .into_iter()
+                .flatten()
+                .map(to_json)
+                .collect::<Result<_>>()?,
+        ),
+        _ => bail!("unsupported param value `{value}`"),
+    })
+}
+
+fn from_json(value: &Json) -> Value {
+    match value {
+        Json::Null => Value::Nil,
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => Value::from(n),
+            (_, Some(n)) => Value::from(n),
+            _ => Value::from(n.as_f64().unwrap_or_default()),
+        },
+        Json::String(s) => Value::string(s.as_str()),
+        Json::Array(values) => Value::vector(values.iter().map(from_json)),
+        Json::Object(object) => to_lexpr(object),
+    }
+}
+
+/// Returns whether `value` is a `(name value)` entry of nested params, named by a symbol or a
+/// string.
+fn is_entry(value: &Value) -> bool {
+    value
+        .as_pair()
+        .is_some_and(|(key, _)| key.is_symbol() || key.is_string())
+}
+
+/// Returns whether `name` can be written as a symbol rather than a string.
+fn is_symbol(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Returns the script setting `flora.params` in the page.
+pub fn params_script(params: &Params) -> String {
+    format!(
+        "(window.flora = window.flora || {{}}).params = {};",
+        Json::from(params.clone())
+    )
+}
+
+/// Deserializes a param schema from a list, or from the JSON string the flora format is normalized
+/// into, like params.
+pub fn deserialize_schema<'de, D>(deserializer: D) -> Result<Option<Vec<ParamSpec>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct SchemaVisitor;
+
+    impl<'de> de::Visitor<'de> for SchemaVisitor {
+        type Value = Option<Vec<ParamSpec>>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a list of params")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            serde_json::from_str(value).map(Some).map_err(E::custom)
+        }
+
+        fn visit_seq<A: de::SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+            Vec::deserialize(de::value::SeqAccessDeserializer::new(seq)).map(Some)
+        }
+    }
+
+    deserializer.deserialize_any(SchemaVisitor)
+}
+
+/// Deserializes params from a table, or from the JSON string the flora format is normalized into,
+/// since serde-lexpr can't deserialize free-form values.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Params>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct ParamsVisitor;
+
+    impl<'de> de::Visitor<'de> for ParamsVisitor {
+        type Value = Option<Params>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a table of params")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+            serde_json::from_str(value).map(Some).map_err(E::custom)
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+            Params::deserialize(de::value::MapAccessDeserializer::new(map)).map(Some)
+        }
+    }
+
+    deserializer.deserialize_any(ParamsVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        from_lexpr, params_script, schema_from_lexpr, schema_to_lexpr, to_lexpr, validate,
+        ParamSpec, ParamType, Params,
+    };
+
+    fn params(value: serde_json::Value) -> Params {
+        match value {
+            serde_json::Value::Object(params) => params,
+            _ => panic!("{value} is not an object"),
+        }
+    }
+
+    #[test]
+    fn test_from_lexpr() {
+        let params = |s: &str| from_lexpr(&s.parse().unwrap()).map(serde_json::Value::Object);
+
+        assert_eq!(
+            params(
+                r#"((timezone "UTC")
+                    (threshold 80)
+                    (ratio 0.5)
+                    (unit celsius)
+                    (compact)
+                    (hidden #f)
+                    (missing #nil)
+                    (sensors #("cpu" "gpu"))
+                    (ranges ((0 10) (10 20)))
+                    (empty ())
+                    (none #())
+                    (location (lat 59.9) (lon 10.7))
+                    ("display name" "Kitchen")
+                    (dotted . 1))"#
+            )
+            .unwrap(),
+            json!({
+                "timezone": "UTC",
+                "threshold": 80,
+                "ratio": 0.5,
+                "unit": "celsius",
+                "compact": true,
+                "hidden": false,
+                "missing": null,
+                "sensors": ["cpu", "gpu"],
+                "ranges": [[0, 10], [10, 20]],
+                "empty": {},
+                "none": [],
+                "location": { "lat": 59.9, "lon": 10.7 },
+                "display name": "Kitchen",
+                "dotted": 1,
+            })
+        );
+
+        assert!(params("((a 1 2))").is_err());
+        assert!(params("((1 2))").is_err());
+        assert!(params("(a)").is_err());
+        assert!(params("5").is_err());
+    }
+
+    #[test]
+    fn test_lexpr_round_trip() {
+        let params = json!({
+            "timezone": "UTC",
+            "threshold": -80,
+            "big": u64::MAX,
+            "ratio": 0.25,
+            "flags": [true, false, null],
+            "location": { "lat": 59.9, "tags": ["a", { "b": 1 }] },
+            "display name": "\"quoted\"",
+            "empty": {},
+            "nested": { "empty": {}, "none": [], "objects": [{}] },
+        });
+        let serde_json::Value::Object(params) = params else {
+            unreachable!()
+        };
+
+        let written = to_lexpr(&params).to_string();
+        assert_eq!(from_lexpr(&written.parse().unwrap()).unwrap(), params);
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = schema_from_lexpr(
+            &r#"((threshold (type integer) (default 80) (min 0) (max 100))
+                 (ratio (type number) (max 1.0))
+                 (timezone (type string) (default "UTC") (description "Where the clock is"))
+                 (compact (type boolean)))"#
+                .parse()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            schema[0],
+            ParamSpec {
+                name: "threshold".to_string(),
+                ty: ParamType::Integer,
+                default: Some(json!(80)),
+                min: Some(0.0),
+                max: Some(100.0),
+                description: None,
+            }
+        );
+
+        assert_eq!(
+            validate(&schema, &params(json!({ "ratio": 0.5, "compact": true }))).unwrap(),
+            params(json!({ "threshold": 80, "ratio": 0.5, "timezone": "UTC", "compact": true }))
+        );
+        assert_eq!(
+            validate(&schema, &params(json!({ "threshold": 0 }))).unwrap(),
+            params(json!({ "threshold": 0, "timezone": "UTC" }))
+        );
+
+        let error = |value| format!("{:#}", validate(&schema, &params(value)).unwrap_err());
+        assert_eq!(
+            error(json!({ "threshold": 101 })),
+            "invalid param `threshold`: 101 is outside of 0 to 100"
+        );
+        assert_eq!(
+            error(json!({ "threshold": 1.5 })),
+            "invalid param `threshold`: expected an integer, but got `1.5`"
+        );
+        assert_eq!(
+            error(json!({ "compact": "yes" })),
+            "invalid param `compact`: expected a boolean, but got `\"yes\"`"
+        );
+        assert_eq!(error(json!({ "treshold": 50 })), "unknown param `treshold`");
+
+        let invalid_schemas = [
+            "((a (type number) (default \"1\")))",
+            "((a (type string) (min 1)))",
+            "((a (type number) (min 2) (max 1)))",
+            "((a (type number)) (a (type string)))",
+            "((a (type color)))",
+            "((a (type number) (step 2)))",
+            "((a (default 1)))",
+        ];
+        for schema in invalid_schemas {
+            let schema = schema_from_lexpr(&schema.parse().unwrap())
+                .and_then(|schema| validate(&schema, &Params::new()));
+            assert!(schema.is_err(), "{schema:?}");
+        }
+    }
+
+    #[test]
+    fn test_schema_lexpr_round_trip() {
+        let schema = vec![
+            ParamSpec {
+                name: "threshold".to_string(),
+                ty: ParamType::Integer,
+                default: Some(json!(80)),
+                min: Some(0.0),
+                max: Some(100.5),
+                description: Some("When to \"warn\"".to_string()),
+            },
+            ParamSpec {
+                name: "display name".to_string(),
+                ty: ParamType::String,
+                default: None,
+                min: None,
+                max: None,
+                description: None,
+            },
+        ];
+
+        let written = schema_to_lexpr(&schema).to_string();
+        assert_eq!(
+            schema_from_lexpr(&written.parse().unwrap()).unwrap(),
+            schema
+        );
+    }
+
+    #[test]
+    fn test_params_script() {
+        let serde_json::Value::Object(params) = json!({ "x": "</script>'\"" }) else {
+            unreachable!()
+        };
+        assert_eq!(
+            params_script(&params),
+            r#"(window.flora = window.flora || {}).params = {"x":"</script>'\""};"#
+        );
+    }
+}
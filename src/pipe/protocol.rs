@@ -7,8 +7,10 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::{click_through::ClickThrough, layer::Layer};
+
 /// Represents accepted actions to the server by the client.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ServerRequest {
     /// Get the name of the widget.
     GetName,
@@ -16,6 +18,18 @@ pub enum ServerRequest {
     GetContent,
     ShowWindow,
     HideWindow,
+    /// Show the widget if it is hidden, and hide it otherwise.
+    ToggleWindow,
+    /// Load the content of the widget again.
+    Reload,
+    /// Move the widget into the given z-order layer.
+    SetLayer(Layer),
+    /// Change whether mouse input passes through the widget.
+    SetClickThrough(ClickThrough),
+    /// Enter or leave edit mode, where the widget can be moved and resized with the mouse.
+    ToggleEditMode,
+    /// Show the settings page of the widget in place of its content.
+    OpenSettings,
 }
 
 /// Represents a server response
@@ -5,14 +5,10 @@ use tokio::{
     io::Interest,
     net::windows::named_pipe::{NamedPipeServer, ServerOptions},
 };
-use tracing::{info, trace};
+use tracing::{info, trace, warn};
 use windows::Win32::Foundation::ERROR_NO_DATA;
 
-use crate::{
-    execute,
-    windows_api::{self},
-    CONTENT, NAME,
-};
+use crate::{execute, CLICK_THROUGH, CONTENT, LAYER, NAME};
 
 use super::{
     create_pipe_name,
@@ -93,7 +89,7 @@ async fn handle_client(client: NamedPipeServer) -> Result<()> {
 }
 
 #[tracing::instrument(level = "trace")]
-fn handle_request(request: ServerRequest) -> ServerResponse {
+pub fn handle_request(request: ServerRequest) -> ServerResponse {
     return match request {
         ServerRequest::GetName => {
             let name = NAME.lock().unwrap();
@@ -105,7 +101,9 @@ fn handle_request(request: ServerRequest) -> ServerResponse {
         }
         ServerRequest::ShowWindow => {
             return execute(|webview| {
-                windows_api::show_window(*webview.hwnd);
+                if let Err(e) = webview.show() {
+                    warn!("could not show window: {e}");
+                }
             })
             .map_or_else(
                 |e| ServerResponse::Err(format!("{e:?}")),
@@ -114,7 +112,85 @@ fn handle_request(request: ServerRequest) -> ServerResponse {
         }
         ServerRequest::HideWindow => {
             return execute(|webview| {
-                windows_api::hide_window(*webview.hwnd);
+                if let Err(e) = webview.hide() {
+                    warn!("could not hide window: {e}");
+                }
+            })
+            .map_or_else(
+                |e| ServerResponse::Err(format!("{e:?}")),
+                |_| ServerResponse::Ok,
+            );
+        }
+        ServerRequest::ToggleWindow => {
+            return execute(|webview| {
+                if let Err(e) = webview.toggle() {
+                    warn!("could not toggle window: {e}");
+                }
+            })
+            .map_or_else(
+                |e| ServerResponse::Err(format!("{e:?}")),
+                |_| ServerResponse::Ok,
+            );
+        }
+        ServerRequest::Reload => {
+            return execute(|webview| {
+                if let Err(e) = webview.reload() {
+                    warn!("could not reload content: {e}");
+                }
+            })
+            .map_or_else(
+                |e| ServerResponse::Err(format!("{e:?}")),
+                |_| ServerResponse::Ok,
+            );
+        }
+        ServerRequest::SetLayer(layer) => {
+            {
+                let mut l = LAYER.lock().unwrap();
+                *l = layer;
+            }
+
+            return execute(move |webview| {
+                if let Err(e) = webview.set_layer(layer) {
+                    warn!(?layer, "could not set window layer: {e}");
+                }
+            })
+            .map_or_else(
+                |e| ServerResponse::Err(format!("{e:?}")),
+                |_| ServerResponse::Ok,
+            );
+        }
+        ServerRequest::SetClickThrough(mode) => {
+            {
+                let mut c = CLICK_THROUGH.lock().unwrap();
+                *c = mode;
+            }
+
+            return execute(move |webview| {
+                if let Err(e) = webview.set_click_through(mode) {
+                    warn!(?mode, "could not set click-through mode: {e}");
+                }
+            })
+            .map_or_else(
+                |e| ServerResponse::Err(format!("{e:?}")),
+                |_| ServerResponse::Ok,
+            );
+        }
+        ServerRequest::ToggleEditMode => {
+            return execute(|webview| {
+                if let Err(e) = webview.toggle_edit_mode() {
+                    warn!("could not toggle edit mode: {e}");
+                }
+            })
+            .map_or_else(
+                |e| ServerResponse::Err(format!("{e:?}")),
+                |_| ServerResponse::Ok,
+            );
+        }
+        ServerRequest::OpenSettings => {
+            return execute(|webview| {
+                if let Err(e) = webview.open_settings() {
+                    warn!("could not open settings: {e}");
+                }
             })
             .map_or_else(
                 |e| ServerResponse::Err(format!("{e:?}")),
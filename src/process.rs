@@ -10,9 +10,12 @@ use windows::Win32::{
     },
 };
 
-use crate::pipe::{
-    self,
-    protocol::{ServerRequest, ServerResponse},
+use crate::{
+    pipe::{
+        self,
+        protocol::{ServerRequest, ServerResponse},
+    },
+    windows_api,
 };
 
 lazy_static! {
@@ -30,6 +33,7 @@ pub struct FloraProcess {
     pub y: i32,
     pub width: i32,
     pub height: i32,
+    pub dpi: u32,
 }
 
 impl FloraProcess {
@@ -86,6 +90,7 @@ pub unsafe extern "system" fn enum_windows_callback(hwnd: HWND, lparam: LPARAM)
                     y: rect.top,
                     width: rect.right - rect.left,
                     height: rect.bottom - rect.top,
+                    dpi: windows_api::get_window_dpi(hwnd),
                 });
             }
             _ => (),
@@ -0,0 +1,251 @@
+//! The page side of bindings, which turns calls into messages to the window and settles them once
+//! the window replies.
+//!
+//! [`BOOTSTRAP_SCRIPT`] is the only script defining how calls work. Everything else sent to the page
+//! is data: the names of the bindings, the id of a call and its result, always encoded as JSON, so
+//! neither a binding name nor a result can break out of the script it is passed to.
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+/// Defines `window._rpc`, which binds functions by name and settles their calls. Calls are
+/// rejected with a timeout error if the window doesn't reply within `window._rpc.timeout`
+/// milliseconds, unless it is 0, and cancelled in the window.
+///
+/// Streaming bindings return an async iterator instead of a promise. The iterator asks the window
+/// for one more item whenever it hands an item to the page, and cancels the call when the page
+/// stops iterating early, e.g. by breaking out of a `for await` loop.
+pub const BOOTSTRAP_SCRIPT: &str = r#"
+(function() {
+    var calls = {};
+    var streams = {};
+    var RPC = window._rpc = { nextSeq: 1, timeout: 0 };
+    window.external = {
+        invoke: function(message) {
+            window.chrome.webview.postMessage(message);
+        },
+    };
+
+    function control(id, action, count) {
+        window.external.invoke({ id: id, control: action, count: count });
+    }
+
+    function bind(name) {
+        window[name] = function() {
+            var seq = RPC.nextSeq++;
+            var params = Array.prototype.slice.call(arguments);
+            return new Promise(function(resolve, reject) {
+                var timeout = RPC.timeout;
+                var timer = timeout > 0 && setTimeout(function() {
+                    delete calls[seq];
+                    control(seq, "cancel");
+                    reject({
+                        code: -32001,
+                        message: "`" + name + "` timed out after " + timeout + " ms",
+                        data: { method: name, timeout: timeout },
+                    });
+                }, timeout);
+                calls[seq] = function(ok, result) {
+                    clearTimeout(timer);
+                    (ok ? resolve : reject)(result);
+                };
+                window.external.invoke({ id: seq, method: name, params: params });
+            });
+        };
+    }
+
+    function stream(name) {
+        window[name] = function() {
+            var seq = RPC.nextSeq++;
+            var params = Array.prototype.slice.call(arguments);
+            // items received but not read yet, reads waiting for an item, and how the stream ended
+            var items = [];
+            var reads = [];
+            var end = null;
+
+            function flush() {
+                while (reads.length && (items.length || end)) {
+                    var read = reads.shift();
+                    if (items.length) {
+                        read.resolve({ value: items.shift(), done: false });
+                        control(seq, "pull", 1);
+                    } else if (end.ok) {
+                        read.resolve({ value: undefined, done: true });
+                    } else {
+                        read.reject(end.error);
+                        end = { ok: true };
+                    }
+                }
+            }
+
+            streams[seq] = function(item) {
+                items.push(item);
+                flush();
+            };
+            calls[seq] = function(ok, result) {
+                delete streams[seq];
+                end = { ok: ok, error: result };
+                flush();
+            };
+            window.external.invoke({ id: seq, method: name, params: params });
+
+            var iterator = {
+                next: function() {
+                    return new Promise(function(resolve, reject) {
+                        reads.push({ resolve: resolve, reject: reject });
+                        flush();
+                    });
+                },
+                return: function() {
+                    if (!end) {
+                        delete calls[seq];
+                        delete streams[seq];
+                        control(seq, "cancel");
+                    }
+                    items = [];
+                    end = { ok: true };
+                    flush();
+                    return Promise.resolve({ value: undefined, done: true });
+                },
+            };
+            iterator[Symbol.asyncIterator] = function() {
+                return iterator;
+            };
+            return iterator;
+        };
+    }
+
+    RPC.bind = function(names, streaming) {
+        names.forEach(streaming ? stream : bind);
+    };
+
+    RPC.push = function(id, item) {
+        var push = streams[id];
+        if (push) {
+            push(item);
+        }
+    };
+
+    // calls that timed out are gone, and their result is dropped
+    RPC.settle = function(id, ok, result) {
+        var settle = calls[id];
+        if (settle) {
+            delete calls[id];
+            settle(ok, result);
+        }
+    };
+})();
+"#;
+
+/// Checks that `name` is a plain JavaScript identifier, so it can be used as the name of a
+/// function on `window`.
+pub fn validate_name(name: &str) -> Result<()> {
+    if !is_identifier(name) {
+        bail!("invalid binding name {name:?}: names must be JavaScript identifiers");
+    }
+
+    Ok(())
+}
+
+/// Returns whether `name` is a JavaScript identifier made of ASCII letters, digits, `_` and `$`.
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Returns the script defining the functions of the given bindings on `window`, which are
+/// streaming bindings if `stream` is true.
+pub fn bind_script(names: &[&str], stream: bool) -> String {
+    format!("window._rpc.bind({}, {stream});", Value::from(names))
+}
+
+/// Returns the script passing the next item of the streaming call with the given id to the page.
+pub fn push_script(id: u64, item: &Value) -> String {
+    format!("window._rpc.push({id}, {item});")
+}
+
+/// Returns the script settling the call with the given id, resolving it with `result` if `ok` is
+/// true and rejecting it otherwise.
+pub fn settle_script(id: u64, ok: bool, result: &Value) -> String {
+    format!("window._rpc.settle({id}, {ok}, {result});")
+}
+
+/// Returns the script setting how long calls wait for a result, in milliseconds.
+pub fn timeout_script(timeout: u32) -> String {
+    format!("window._rpc.timeout = {timeout};")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{bind_script, push_script, settle_script, validate_name};
+
+    /// Returns the JSON arguments of a generated `window._rpc.<method>(...)` call, checking that
+    /// nothing else was added to the script.
+    fn arguments(script: &str, method: &str) -> Value {
+        let args = script
+            .strip_prefix(&format!("window._rpc.{method}("))
+            .and_then(|s| s.strip_suffix(");"))
+            .unwrap();
+        serde_json::from_str(&format!("[{args}]")).unwrap()
+    }
+
+    #[test]
+    fn test_validate_name() {
+        for name in ["__floraHide", "getInfo", "$", "_a1", "fetch2"] {
+            assert!(validate_name(name).is_ok(), "{name}");
+        }
+
+        let hostile = [
+            "",
+            "1st",
+            "get info",
+            "a-b",
+            "a.b",
+            "a'b",
+            "a\"b",
+            "';alert(1);'",
+            "\"]);alert(1);//",
+            "a\u{2028}b",
+            "naïve",
+            "</script>",
+        ];
+        for name in hostile {
+            assert!(validate_name(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_scripts_only_carry_data() {
+        assert_eq!(
+            bind_script(&["__floraHide", "getInfo"], false),
+            r#"window._rpc.bind(["__floraHide","getInfo"], false);"#
+        );
+        // names are validated before they are bound, but are still passed as data
+        let name = "\"]);alert(1);//";
+        assert_eq!(
+            arguments(&bind_script(&[name], true), "bind"),
+            json!([[name], true])
+        );
+
+        let payloads = [
+            json!("'); alert(1); //"),
+            json!("\"); alert(1); //"),
+            json!("</script><script>alert(1)</script>"),
+            json!("line\u{2028}separator\u{2029}and\nnewline\\"),
+            json!({ "code": -32000, "message": "`x` failed: \"quoted\"", "data": null }),
+            json!([null, 1.5, { "}); alert(1); ({": true }]),
+        ];
+        for payload in payloads {
+            for ok in [true, false] {
+                let script = settle_script(7, ok, &payload);
+                assert_eq!(arguments(&script, "settle"), json!([7, ok, payload]));
+            }
+            let script = push_script(7, &payload);
+            assert_eq!(arguments(&script, "push"), json!([7, payload]));
+        }
+    }
+}
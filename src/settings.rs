@@ -0,0 +1,142 @@
+//! The settings page, generated from the param schema of a widget, which shows a field for every
+//! param and writes the params back into the config.
+//!
+//! The page is shown in place of the content of the widget. Like the rest of the page side, it is
+//! static: it reads the schema and the params through bindings, so nothing from the config ends
+//! up in its markup.
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::params::{ParamSpec, Params};
+
+/// The name of the binding returning the param schema and the current params.
+pub const GET_SETTINGS_BINDING: &str = "__floraGetSettings";
+/// The name of the binding checking params against the schema and writing them into the config.
+pub const SAVE_SETTINGS_BINDING: &str = "__floraSaveSettings";
+/// The name of the binding leaving the settings page for the content of the widget.
+pub const CLOSE_SETTINGS_BINDING: &str = "__floraCloseSettings";
+
+/// What the settings page shows.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Settings {
+    pub schema: Vec<ParamSpec>,
+    /// The params the config sets, without the defaults of the schema, which the page shows as
+    /// placeholders instead.
+    pub params: Params,
+}
+
+/// The settings page. Fields left empty, and checkboxes left at their default, are left out of the
+/// params, so they keep following the defaults of the schema.
+pub const SETTINGS_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Settings</title>
+<style>
+    body {
+        margin: 0;
+        padding: 12px;
+        font: 13px system-ui, sans-serif;
+        color: #e0e0e0;
+        background: #1e1e2e;
+    }
+    h1 { margin: 0 0 12px; font-size: 15px; }
+    label { display: block; margin-bottom: 10px; }
+    .name { display: block; font-weight: 600; }
+    .description { display: block; margin: 2px 0 4px; color: #a0a0b0; }
+    input[type=text], input[type=number] { box-sizing: border-box; width: 100%; }
+    .buttons { display: flex; gap: 8px; margin-top: 12px; }
+    #status { margin-top: 8px; color: #f38ba8; }
+</style>
+</head>
+<body>
+<h1>Settings</h1>
+<form id="settings"></form>
+<div class="buttons">
+    <button id="save" type="button">Save</button>
+    <button id="cancel" type="button">Cancel</button>
+</div>
+<div id="status"></div>
+<script>
+(function() {
+    var form = document.getElementById("settings");
+    var status = document.getElementById("status");
+    var fields = [];
+
+    function field(spec, value) {
+        var label = document.createElement("label");
+        var name = document.createElement("span");
+        name.className = "name";
+        name.textContent = spec.name;
+        label.appendChild(name);
+        if (spec.description) {
+            var description = document.createElement("span");
+            description.className = "description";
+            description.textContent = spec.description;
+            label.appendChild(description);
+        }
+
+        var input = document.createElement("input");
+        if (spec.type === "boolean") {
+            input.type = "checkbox";
+            input.checked = value === undefined ? spec.default === true : value === true;
+        } else if (spec.type === "string") {
+            input.type = "text";
+            input.value = value === undefined ? "" : value;
+        } else {
+            input.type = "number";
+            input.step = spec.type === "integer" ? "1" : "any";
+            if (spec.min !== undefined) input.min = spec.min;
+            if (spec.max !== undefined) input.max = spec.max;
+            input.value = value === undefined ? "" : value;
+        }
+        if (spec.default !== undefined && input.type !== "checkbox") {
+            input.placeholder = String(spec.default);
+        }
+        label.appendChild(input);
+        form.appendChild(label);
+        fields.push({ spec: spec, input: input, set: value !== undefined });
+    }
+
+    function params() {
+        var params = {};
+        fields.forEach(function(field) {
+            var input = field.input;
+            if (input.type === "checkbox") {
+                if (field.set || input.checked !== (field.spec.default === true)) {
+                    params[field.spec.name] = input.checked;
+                }
+            } else if (input.value !== "") {
+                params[field.spec.name] = input.type === "number" ? Number(input.value) : input.value;
+            }
+        });
+        return params;
+    }
+
+    function fail(e) {
+        status.textContent = e && e.message ? e.message : String(e);
+    }
+
+    window.__floraGetSettings().then(function(settings) {
+        if (!settings.schema.length) {
+            status.textContent = "This widget doesn't declare any params.";
+        }
+        settings.schema.forEach(function(spec) {
+            field(spec, settings.params[spec.name]);
+        });
+    }, fail);
+
+    document.getElementById("save").addEventListener("click", function() {
+        status.textContent = "";
+        window.__floraSaveSettings(params()).then(function() {
+            return window.__floraCloseSettings();
+        }).catch(fail);
+    });
+    document.getElementById("cancel").addEventListener("click", function() {
+        window.__floraCloseSettings().catch(fail);
+    });
+})();
+</script>
+</body>
+</html>
+"#;
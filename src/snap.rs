@@ -0,0 +1,255 @@
+//! Snaps the bounds of a widget to a grid and to the edges of its monitor while it is moved or
+//! resized in edit mode.
+use crate::layout::Rect;
+
+/// How the bounds of a widget snap while it is moved or resized. Both kinds of snapping are
+/// disabled by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Snap {
+    /// The size of the grid in pixels, starting at the top left corner of the monitor.
+    pub grid: Option<u32>,
+    /// How close, in pixels, an edge of the widget must be to an edge of the monitor to snap to it.
+    /// Edge snapping takes precedence over the grid.
+    pub edges: Option<u32>,
+}
+
+/// The edges of a rect that are being moved while resizing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Edges {
+    pub left: bool,
+    pub top: bool,
+    pub right: bool,
+    pub bottom: bool,
+}
+
+impl Snap {
+    /// Snaps a coordinate to the monitor edges at `start` and `end`, if it is close enough to
+    /// either of them.
+    fn snap_to_edges(&self, value: i32, start: i32, end: i32) -> Option<i32> {
+        let distance = self.edges? as i32;
+
+        [start, end]
+            .into_iter()
+            .filter(|edge| (value - edge).abs() <= distance)
+            .min_by_key(|edge| (value - edge).abs())
+    }
+
+    /// Snaps a coordinate to the closest grid line, where the grid starts at `start`.
+    fn snap_to_grid(&self, value: i32, start: i32) -> i32 {
+        match self.grid {
+            Some(grid) if grid > 1 => {
+                let grid = grid as f64;
+                start + (((value - start) as f64 / grid).round() * grid) as i32
+            }
+            _ => value,
+        }
+    }
+
+    /// Snaps a single edge along one axis, where the monitor spans `start` to `end`.
+    fn snap_edge(&self, value: i32, start: i32, end: i32) -> i32 {
+        self.snap_to_edges(value, start, end)
+            .unwrap_or_else(|| self.snap_to_grid(value, start))
+    }
+
+    /// Snaps the position of a span of `size` at `position` along one axis. Either end of the span
+    /// can snap to a monitor edge, in which case the closest one wins; otherwise, the start of the
+    /// span snaps to the grid.
+    fn snap_span(&self, position: i32, size: i32, start: i32, end: i32) -> i32 {
+        let snapped_start = self.snap_to_edges(position, start, end);
+        let snapped_end = self
+            .snap_to_edges(position + size, start, end)
+            .map(|snapped| snapped - size);
+
+        match (snapped_start, snapped_end) {
+            (Some(a), Some(b)) if (b - position).abs() < (a - position).abs() => b,
+            (Some(a), _) => a,
+            (None, Some(b)) => b,
+            (None, None) => self.snap_to_grid(position, start),
+        }
+    }
+
+    /// Snaps a rect that is being moved within the monitor, keeping its size.
+    pub fn snap_move(&self, rect: &Rect, monitor: &Rect) -> Rect {
+        Rect {
+            x: self.snap_span(rect.x, rect.width, monitor.x, monitor.x + monitor.width),
+            y: self.snap_span(rect.y, rect.height, monitor.y, monitor.y + monitor.height),
+            ..*rect
+        }
+    }
+
+    /// Snaps the given edges of a rect that is being resized within the monitor. Edges are left
+    /// unsnapped if snapping would collapse the rect.
+    pub fn snap_resize(&self, rect: &Rect, edges: Edges, monitor: &Rect) -> Rect {
+        let (monitor_right, monitor_bottom) =
+            (monitor.x + monitor.width, monitor.y + monitor.height);
+        let snap = |moving: bool, value: i32, start: i32, end: i32| match moving {
+            true => self.snap_edge(value, start, end),
+            false => value,
+        };
+
+        let mut left = snap(edges.left, rect.x, monitor.x, monitor_right);
+        let mut top = snap(edges.top, rect.y, monitor.y, monitor_bottom);
+        let mut right = snap(edges.right, rect.x + rect.width, monitor.x, monitor_right);
+        let mut bottom = snap(
+            edges.bottom,
+            rect.y + rect.height,
+            monitor.y,
+            monitor_bottom,
+        );
+
+        if right <= left {
+            (left, right) = (rect.x, rect.x + rect.width);
+        }
+        if bottom <= top {
+            (top, bottom) = (rect.y, rect.y + rect.height);
+        }
+
+        Rect {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::Rect;
+
+    use super::{Edges, Snap};
+
+    const MONITOR: Rect = Rect {
+        x: -1920,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+
+    fn rect(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_no_snapping() {
+        let snap = Snap::default();
+        let moved = rect(-1913, 7, 200, 20);
+
+        assert_eq!(snap.snap_move(&moved, &MONITOR), moved);
+        assert_eq!(
+            snap.snap_resize(
+                &moved,
+                Edges {
+                    right: true,
+                    ..Default::default()
+                },
+                &MONITOR
+            ),
+            moved
+        );
+    }
+
+    #[test]
+    fn test_snap_move_to_grid() {
+        let snap = Snap {
+            grid: Some(10),
+            edges: None,
+        };
+
+        // the grid starts at the monitor origin
+        assert_eq!(
+            snap.snap_move(&rect(-1913, 7, 200, 20), &MONITOR),
+            rect(-1910, 10, 200, 20)
+        );
+        assert_eq!(
+            snap.snap_move(&rect(-1916, 14, 200, 20), &MONITOR),
+            rect(-1920, 10, 200, 20)
+        );
+    }
+
+    #[test]
+    fn test_snap_move_to_edges() {
+        let snap = Snap {
+            grid: Some(50),
+            edges: Some(8),
+        };
+
+        // the left and top edges are close to the monitor edges
+        assert_eq!(
+            snap.snap_move(&rect(-1914, 5, 200, 20), &MONITOR),
+            rect(-1920, 0, 200, 20)
+        );
+        // the right and bottom edges are close to the monitor edges
+        assert_eq!(
+            snap.snap_move(&rect(-205, 1052, 200, 20), &MONITOR),
+            rect(-200, 1060, 200, 20)
+        );
+        // too far from the edges, so the grid is used
+        assert_eq!(
+            snap.snap_move(&rect(-1880, 30, 200, 20), &MONITOR),
+            rect(-1870, 50, 200, 20)
+        );
+    }
+
+    #[test]
+    fn test_snap_resize() {
+        let snap = Snap {
+            grid: Some(10),
+            edges: Some(8),
+        };
+        let resized = rect(-1913, 7, 203, 1067);
+
+        // only the moving edges snap
+        assert_eq!(
+            snap.snap_resize(
+                &resized,
+                Edges {
+                    right: true,
+                    bottom: true,
+                    ..Default::default()
+                },
+                &MONITOR
+            ),
+            rect(-1913, 7, 203, 1073)
+        );
+        assert_eq!(
+            snap.snap_resize(
+                &resized,
+                Edges {
+                    left: true,
+                    top: true,
+                    ..Default::default()
+                },
+                &MONITOR
+            ),
+            rect(-1920, 0, 210, 1074)
+        );
+    }
+
+    #[test]
+    fn test_snap_resize_does_not_collapse() {
+        let snap = Snap {
+            grid: Some(100),
+            edges: None,
+        };
+        let resized = rect(-1910, 10, 20, 20);
+
+        assert_eq!(
+            snap.snap_resize(
+                &resized,
+                Edges {
+                    right: true,
+                    bottom: true,
+                    ..Default::default()
+                },
+                &MONITOR
+            ),
+            resized
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Persistent key-value storage for widgets, behind `flora.storage` in the page.
+//!
+//! Every widget stores its entries as a JSON object in its own file in the flora data directory,
+//! named after the widget. Files are read on every access and replaced atomically on every change,
+//! so `flora storage` can inspect them while the widget runs, and a crash never leaves a file half
+//! written.
+use std::{
+    collections::HashMap,
+    env,
+    fs::{self, File},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use lazy_static::lazy_static;
+use serde_json::{json, Map, Value};
+
+use crate::binding::RpcError;
+
+lazy_static! {
+    /// A lock for every storage file accessed by this process, held from reading the file until
+    /// the change is written back, so concurrent changes don't drop each other.
+    static ref LOCKS: Mutex<HashMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+/// Counts the writes of this process, to give each its own temporary file.
+static WRITES: AtomicU64 = AtomicU64::new(0);
+
+/// The longest key, in bytes.
+pub const MAX_KEY_LENGTH: usize = 256;
+
+/// How large the storage of a widget may grow, in bytes of JSON.
+pub const QUOTA: usize = 1024 * 1024;
+
+/// The storage of a widget.
+#[derive(Debug)]
+pub struct Storage {
+    path: PathBuf,
+    entries: Map<String, Value>,
+}
+
+/// Returns the directory the storage files of all widgets are kept in.
+///
+/// On Windows this is `%LOCALAPPDATA%/flora/storage`. Elsewhere, `$XDG_DATA_HOME` is used, falling
+/// back to `~/.local/share`.
+pub fn storage_dir() -> Option<PathBuf> {
+    let data_dir = env::var_os("LOCALAPPDATA")
+        .or_else(|| env::var_os("XDG_DATA_HOME"))
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+
+    Some(data_dir.join("flora").join("storage"))
+}
+
+/// Opens the storage of the widget with the given name.
+pub fn open(name: &str) -> Result<Storage> {
+    Storage::open(path(name)?)
+}
+
+/// Opens the storage of the widget with the given name and runs `f` on it, while no other call in
+/// this process accesses the same storage.
+pub fn with_storage<T>(name: &str, f: impl FnOnce(Storage) -> Result<T>) -> Result<T> {
+    with_storage_at(path(name)?, f)
+}
+
+fn with_storage_at<T>(path: PathBuf, f: impl FnOnce(Storage) -> Result<T>) -> Result<T> {
+    let lock = LOCKS
+        .lock()
+        .unwrap()
+        .entry(path.clone())
+        .or_default()
+        .clone();
+    let _guard = lock.lock().unwrap();
+
+    f(Storage::open(path)?)
+}
+
+/// Returns the path of the storage file of the widget with the given name.
+fn path(name: &str) -> Result<PathBuf> {
+    let dir = storage_dir().ok_or_else(|| anyhow!("could not find the flora data directory"))?;
+    Ok(dir.join(file_name(name)))
+}
+
+/// Replaces the file at `path` with `contents`. The contents are written to a temporary file next
+/// to it first, which is then renamed over it, so the file is never left half written.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        WRITES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let temp = PathBuf::from(temp);
+
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    written
+        .and_then(|_| fs::rename(&temp, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+}
+
+/// Returns the name of the storage file of a widget. Anything but ASCII letters, digits, `-` and `_`
+/// is percent-encoded, so every name maps to its own valid file name.
+fn file_name(name: &str) -> String {
+    let mut file_name = String::new();
+    for byte in name.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' => file_name.push(byte as char),
+            _ => file_name += &format!("%{byte:02X}"),
+        }
+    }
+    file_name + ".json"
+}
+
+impl Storage {
+    /// Reads the storage file at `path`, which is empty if the file doesn't exist yet.
+    pub fn open(path: PathBuf) -> Result<Storage> {
+        let entries = match fs::read(&path) {
+            Ok(contents) => serde_json::from_slice(&contents)
+                .with_context(|| format!("could not parse storage {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Map::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("could not read storage {}", path.display()))
+            }
+        };
+
+        Ok(Storage { path, entries })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &Map<String, Value> {
+        &self.entries
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    /// Returns the keys of all entries, in order.
+    pub fn keys(&self) -> Vec<String> {
+        self.entries.keys().cloned().collect()
+    }
+
+    /// Sets the entry with the given key, failing if the storage would grow beyond [`QUOTA`].
+    pub fn set(&mut self, key: String, value: Value) -> Result<()> {
+        if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+            bail!(
+                "keys must be 1 to {MAX_KEY_LENGTH} bytes long, but got {} bytes",
+                key.len()
+            );
+        }
+
+        let previous = self.entries.insert(key.clone(), value);
+        let contents = serde_json::to_vec(&self.entries)?;
+        if contents.len() > QUOTA {
+            match previous {
+                Some(previous) => self.entries.insert(key, previous),
+                None => self.entries.remove(&key),
+            };
+            return Err(RpcError::new(
+                RpcError::QUOTA_EXCEEDED,
+                format!("the storage quota of {QUOTA} bytes would be exceeded"),
+                Some(json!({ "quota": QUOTA, "size": contents.len() })),
+            )
+            .into());
+        }
+
+        self.write(&contents)
+    }
+
+    /// Deletes the entry with the given key, returning whether there was one.
+    pub fn delete(&mut self, key: &str) -> Result<bool> {
+        if self.entries.remove(key).is_none() {
+            return Ok(false);
+        }
+
+        self.write(&serde_json::to_vec(&self.entries)?)?;
+        Ok(true)
+    }
+
+    /// Deletes all entries, along with the storage file.
+    pub fn clear(&mut self) -> Result<()> {
+        self.entries.clear();
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("could not delete storage {}", self.path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Replaces the storage file with `contents`, with [`write_atomically`].
+    fn write(&self, contents: &[u8]) -> Result<()> {
+        let dir = self
+            .path
+            .parent()
+            .ok_or_else(|| anyhow!("invalid storage path {}", self.path.display()))?;
+        fs::create_dir_all(dir)
+            .with_context(|| format!("could not create storage directory {}", dir.display()))?;
+
+        write_atomically(&self.path, contents)
+            .with_context(|| format!("could not write storage {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, thread};
+
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use crate::binding::RpcError;
+
+    use super::{file_name, with_storage_at, Storage, MAX_KEY_LENGTH, QUOTA};
+
+    /// Returns a fresh temporary directory, which is removed once it is dropped, along with the
+    /// path of a storage file in it.
+    fn storage_path() -> (TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("widget.json");
+        (dir, path)
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("clock"), "clock.json");
+        assert_eq!(
+            file_name("Generic Flora Widget"),
+            "Generic%20Flora%20Widget.json"
+        );
+        assert_eq!(file_name("../a/b"), "%2E%2E%2Fa%2Fb.json");
+        assert_eq!(file_name("é"), "%C3%A9.json");
+        assert_eq!(file_name(""), ".json");
+    }
+
+    #[test]
+    fn test_set_get_delete() {
+        let (_dir, path) = storage_path();
+        let mut storage = Storage::open(path.clone()).unwrap();
+        assert_eq!(storage.get("count"), None);
+
+        storage.set("count".to_string(), json!(3)).unwrap();
+        storage
+            .set("dismissed".to_string(), json!(["update", "battery"]))
+            .unwrap();
+        storage.set("count".to_string(), json!(4)).unwrap();
+
+        let mut storage = Storage::open(path.clone()).unwrap();
+        assert_eq!(storage.get("count"), Some(&json!(4)));
+        assert_eq!(storage.keys(), ["count", "dismissed"]);
+
+        assert!(storage.delete("count").unwrap());
+        assert!(!storage.delete("count").unwrap());
+        assert_eq!(Storage::open(path.clone()).unwrap().keys(), ["dismissed"]);
+
+        // only the storage file is left behind
+        let files: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+
+        storage.clear().unwrap();
+        assert!(!path.exists());
+        assert!(Storage::open(path).unwrap().entries().is_empty());
+    }
+
+    #[test]
+    fn test_quota() {
+        let (_dir, path) = storage_path();
+        let mut storage = Storage::open(path.clone()).unwrap();
+
+        assert!(storage.set(String::new(), json!(1)).is_err());
+        assert!(storage
+            .set("k".repeat(MAX_KEY_LENGTH + 1), json!(1))
+            .is_err());
+
+        storage
+            .set("big".to_string(), json!("a".repeat(QUOTA / 2)))
+            .unwrap();
+        let err = storage
+            .set("bigger".to_string(), json!("a".repeat(QUOTA / 2)))
+            .unwrap_err();
+        let err = RpcError::from(err);
+        assert_eq!(err.code, RpcError::QUOTA_EXCEEDED);
+
+        // the failed entry is neither kept nor written
+        assert_eq!(storage.keys(), ["big"]);
+        assert_eq!(Storage::open(path).unwrap().keys(), ["big"]);
+
+        // replacing an entry only counts its new size
+        storage
+            .set("big".to_string(), json!("b".repeat(QUOTA / 2)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_set() {
+        let (_dir, path) = storage_path();
+        let threads: Vec<_> = (0..16)
+            .map(|i| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    with_storage_at(path, |mut storage| storage.set(format!("key{i}"), json!(i)))
+                        .unwrap()
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // no change is lost, and no temporary file is left behind
+        assert_eq!(Storage::open(path.clone()).unwrap().keys().len(), 16);
+        let files: Vec<_> = fs::read_dir(path.parent().unwrap()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_file() {
+        let (_dir, path) = storage_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "[1, 2").unwrap();
+
+        assert!(Storage::open(path).is_err());
+    }
+}
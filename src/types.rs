@@ -0,0 +1,398 @@
+//! TypeScript definitions for the page side of flora, generated from the signatures recorded in a
+//! [`Registry`], so widgets can be type-checked against `window.flora` and the bindings they call.
+//!
+//! Named Rust types become interfaces and type aliases in the `Flora` namespace, so they can't clash
+//! with the globals of the page. Bindings flora uses internally are left out.
+use schemars::schema::{InstanceType, ObjectValidation, Schema, SchemaObject, SingleOrVec};
+use serde_json::Value;
+
+use crate::{
+    api,
+    binding::{Registry, Signature},
+    rpc,
+};
+
+/// The prefix of the bindings flora uses internally, which pages shouldn't call themselves.
+const INTERNAL_PREFIX: &str = "__flora";
+
+/// The namespace named types are declared in.
+const NAMESPACE: &str = "Flora";
+
+/// The description of `flora.params`, which is set by a script rather than a binding.
+const PARAMS_DESCRIPTION: &str =
+    "The params of the widget config, updated along with a `flora:params` event.";
+
+/// Returns the TypeScript definitions of `window.flora`, and of every other binding in `registry`
+/// that isn't internal.
+pub fn declarations(registry: &Registry) -> String {
+    let mut out =
+        String::from("// Type definitions for flora widgets, generated by `flora types`.\n");
+
+    out += &format!("\ndeclare namespace {NAMESPACE} {{\n");
+    for (name, schema) in registry.definitions() {
+        definition(&mut out, name, schema);
+        out.push('\n');
+    }
+    out += "    interface Api {\n";
+    // functions like `storage.get` are grouped into an object, and are listed next to each other
+    let mut group = None;
+    for function in api::FUNCTIONS {
+        let signature = registry
+            .signatures()
+            .find(|signature| signature.name == function.binding);
+        let Some(signature) = signature else {
+            continue;
+        };
+
+        let (function_group, name) = match function.name.split_once('.') {
+            Some((function_group, name)) => (Some(function_group), name),
+            None => (None, function.name),
+        };
+        if function_group != group {
+            if group.is_some() {
+                out += "        };\n";
+            }
+            if let Some(function_group) = function_group {
+                out += &format!("        {function_group}: {{\n");
+            }
+            group = function_group;
+        }
+
+        let indent = match group {
+            Some(_) => "            ",
+            None => "        ",
+        };
+        doc(&mut out, indent, Some(function.description));
+        let declaration = function_type(name, signature, function.params, "");
+        out += &format!("{indent}{declaration};\n");
+    }
+    if group.is_some() {
+        out += "        };\n";
+    }
+    doc(&mut out, "        ", Some(PARAMS_DESCRIPTION));
+    out += "        params: Record<string, unknown>;\n";
+    out += "    }\n}\n";
+    out += &format!("\ndeclare var flora: {NAMESPACE}.Api;\n");
+
+    let prefix = format!("{NAMESPACE}.");
+    for signature in registry.signatures() {
+        if !signature.name.starts_with(INTERNAL_PREFIX) {
+            let declaration = function_type(&signature.name, signature, &[], &prefix);
+            out += &format!("\ndeclare function {declaration};\n");
+        }
+    }
+
+    out
+}
+
+/// Declares a named type, as an interface if it is an object with properties, and as a type alias
+/// otherwise.
+fn definition(out: &mut String, name: &str, schema: &Schema) {
+    doc(out, "    ", description(schema));
+
+    let object = match schema {
+        Schema::Object(SchemaObject {
+            instance_type: Some(SingleOrVec::Single(ty)),
+            object: Some(object),
+            enum_values: None,
+            subschemas: None,
+            ..
+        }) if **ty == InstanceType::Object && !object.properties.is_empty() => object,
+        _ => {
+            *out += &format!("    type {name} = {};\n", ts_type(schema, ""));
+            return;
+        }
+    };
+
+    *out += &format!("    interface {name} {{\n");
+    for (member, description) in members(object, "") {
+        doc(out, "        ", description);
+        *out += &format!("        {member};\n");
+    }
+    *out += "    }\n";
+}
+
+/// Returns the declaration of a function, e.g. `moveTo(x: number, y: number): Promise<Bounds>`.
+/// Parameters are called `arg1`, `arg2`, and so on, unless `names` names them.
+fn function_type(name: &str, signature: &Signature, names: &[&str], prefix: &str) -> String {
+    let params = match &signature.params {
+        Some(params) => {
+            // trailing optional arguments can be left out
+            let required = params
+                .iter()
+                .rposition(|param| !param.name.starts_with("Option<"))
+                .map_or(0, |index| index + 1);
+
+            params
+                .iter()
+                .enumerate()
+                .map(|(index, param)| {
+                    let name = names
+                        .get(index)
+                        .map_or_else(|| format!("arg{}", index + 1), |name| name.to_string());
+                    let optional = if index < required { "" } else { "?" };
+                    format!("{name}{optional}: {}", ts_type(&param.schema, prefix))
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        None => "...args: unknown[]".to_string(),
+    };
+
+    let returns = match &signature.returns {
+        Some(ty) if ty.name == "()" => "void".to_string(),
+        Some(ty) => ts_type(&ty.schema, prefix),
+        None => "unknown".to_string(),
+    };
+    let returns = match signature.stream {
+        true => format!("AsyncIterableIterator<{returns}>"),
+        false => format!("Promise<{returns}>"),
+    };
+
+    format!("{name}({params}): {returns}")
+}
+
+/// Returns the TypeScript type of the JSON described by `schema`. Named types are referred to by
+/// their name, with `prefix` in front.
+fn ts_type(schema: &Schema, prefix: &str) -> String {
+    let schema = match schema {
+        Schema::Bool(true) => return "unknown".to_string(),
+        Schema::Bool(false) => return "never".to_string(),
+        Schema::Object(schema) => schema,
+    };
+
+    if let Some(reference) = &schema.reference {
+        let name = reference.rsplit('/').next().unwrap_or(reference);
+        return format!("{prefix}{name}");
+    }
+    if let Some(value) = &schema.const_value {
+        return value.to_string();
+    }
+    if let Some(values) = &schema.enum_values {
+        return union(values.iter().map(Value::to_string));
+    }
+    if let Some(subschemas) = &schema.subschemas {
+        if let Some(all_of) = &subschemas.all_of {
+            return all_of
+                .iter()
+                .map(|schema| group(ts_type(schema, prefix)))
+                .collect::<Vec<_>>()
+                .join(" & ");
+        }
+        if let Some(any_of) = subschemas.any_of.as_ref().or(subschemas.one_of.as_ref()) {
+            return union(any_of.iter().map(|schema| ts_type(schema, prefix)));
+        }
+    }
+
+    match &schema.instance_type {
+        Some(SingleOrVec::Single(ty)) => instance_type(ty, schema, prefix),
+        Some(SingleOrVec::Vec(types)) => {
+            union(types.iter().map(|ty| instance_type(ty, schema, prefix)))
+        }
+        None => "unknown".to_string(),
+    }
+}
+
+/// Returns the TypeScript type of one of the JSON types `schema` allows.
+fn instance_type(ty: &InstanceType, schema: &SchemaObject, prefix: &str) -> String {
+    match ty {
+        InstanceType::Null => "null".to_string(),
+        InstanceType::Boolean => "boolean".to_string(),
+        InstanceType::Integer | InstanceType::Number => "number".to_string(),
+        InstanceType::String => "string".to_string(),
+        InstanceType::Array => match schema.array.as_ref().and_then(|array| array.items.as_ref()) {
+            Some(SingleOrVec::Single(item)) => format!("{}[]", group(ts_type(item, prefix))),
+            Some(SingleOrVec::Vec(items)) => {
+                let items: Vec<_> = items.iter().map(|item| ts_type(item, prefix)).collect();
+                format!("[{}]", items.join(", "))
+            }
+            None => "unknown[]".to_string(),
+        },
+        InstanceType::Object => {
+            let Some(object) = schema.object.as_deref() else {
+                return "Record<string, unknown>".to_string();
+            };
+            // maps only describe their values
+            if object.properties.is_empty() {
+                let values = object
+                    .additional_properties
+                    .as_deref()
+                    .map_or("unknown".to_string(), |values| ts_type(values, prefix));
+                return format!("Record<string, {values}>");
+            }
+
+            let members: Vec<_> = members(object, prefix)
+                .into_iter()
+                .map(|(member, _)| member)
+                .collect();
+            format!("{{ {} }}", members.join("; "))
+        }
+    }
+}
+
+/// Returns the members of an object type, e.g. `name?: string`, with the description of each.
+fn members<'a>(object: &'a ObjectValidation, prefix: &str) -> Vec<(String, Option<&'a str>)> {
+    let mut members: Vec<_> = object
+        .properties
+        .iter()
+        .map(|(name, schema)| {
+            let key = match rpc::is_identifier(name) {
+                true => name.clone(),
+                false => Value::from(name.as_str()).to_string(),
+            };
+            let optional = if object.required.contains(name) {
+                ""
+            } else {
+                "?"
+            };
+            let member = format!("{key}{optional}: {}", ts_type(schema, prefix));
+            (member, description(schema))
+        })
+        .collect();
+
+    if let Some(additional) = &object.additional_properties {
+        if **additional != Schema::Bool(false) {
+            let member = format!("[key: string]: {}", ts_type(additional, prefix));
+            members.push((member, None));
+        }
+    }
+
+    members
+}
+
+fn description(schema: &Schema) -> Option<&str> {
+    match schema {
+        Schema::Object(schema) => schema.metadata.as_ref()?.description.as_deref(),
+        Schema::Bool(_) => None,
+    }
+}
+
+/// Writes `description` as a doc comment, if there is one.
+fn doc(out: &mut String, indent: &str, description: Option<&str>) {
+    let Some(description) = description else {
+        return;
+    };
+
+    let description = description.replace("*/", "*\\/");
+    if !description.contains('\n') {
+        *out += &format!("{indent}/** {description} */\n");
+        return;
+    }
+
+    *out += &format!("{indent}/**\n");
+    for line in description.lines() {
+        *out += format!("{indent} * {line}").trim_end();
+        out.push('\n');
+    }
+    *out += &format!("{indent} */\n");
+}
+
+fn union(types: impl Iterator<Item = String>) -> String {
+    let types: Vec<_> = types.collect();
+    match types.is_empty() {
+        true => "never".to_string(),
+        false => types.join(" | "),
+    }
+}
+
+/// Wraps a union or intersection in parentheses, so it can be used as part of another type.
+fn group(ty: String) -> String {
+    match ty.contains(" | ") || ty.contains(" & ") {
+        true => format!("({ty})"),
+        false => ty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use schemars::{gen::SchemaGenerator, JsonSchema};
+    use serde::Serialize;
+
+    use crate::{
+        api,
+        binding::{Binding, Registry, Sink, StreamBinding},
+        layer::Layer,
+    };
+
+    use super::{declarations, ts_type};
+
+    #[test]
+    fn test_declarations_in_sync() {
+        let mut registry = Registry::default();
+        api::register(&mut registry);
+
+        assert_eq!(declarations(&registry), include_str!("../docs/flora.d.ts"));
+    }
+
+    #[test]
+    fn test_ts_type() {
+        fn ts<T: JsonSchema>() -> String {
+            let mut generator = SchemaGenerator::default();
+            ts_type(&generator.subschema_for::<T>(), "Flora.")
+        }
+
+        assert_eq!(ts::<Option<String>>(), "string | null");
+        assert_eq!(ts::<Vec<Option<f64>>>(), "(number | null)[]");
+        assert_eq!(ts::<(i32, String)>(), "[number, string]");
+        assert_eq!(ts::<HashMap<String, bool>>(), "Record<string, boolean>");
+        assert_eq!(ts::<Layer>(), "Flora.Layer");
+        assert_eq!(ts::<Option<Layer>>(), "Flora.Layer | null");
+        assert_eq!(ts::<serde_json::Value>(), "unknown");
+    }
+
+    #[test]
+    fn test_declare_bindings() {
+        #[derive(Serialize, JsonSchema)]
+        #[serde(rename_all = "kebab-case")]
+        struct Reading {
+            /// In degrees Celsius.
+            temperature: f64,
+            sensor_name: Option<String>,
+        }
+
+        fn register<F: Binding<Args>, Args>(registry: &mut Registry, name: &str, _f: F) {
+            registry.register::<F, Args>(name);
+        }
+        fn register_stream<F: StreamBinding<Args, T>, Args, T>(
+            registry: &mut Registry,
+            name: &str,
+            _f: F,
+        ) {
+            registry.register_stream::<F, Args, T>(name);
+        }
+
+        let mut registry = Registry::default();
+        register(&mut registry, "read", |_id: u32, _fresh: Option<bool>| {
+            Ok(Vec::<Reading>::new())
+        });
+        register(&mut registry, "__floraInternal", || Ok(()));
+        registry.register_untyped("raw");
+        register_stream(
+            &mut registry,
+            "watch",
+            |_id: u32, _sink: Sink<Reading>| async { Ok(()) },
+        );
+
+        let declarations = declarations(&registry);
+        assert!(declarations.contains(
+            r#"    interface Reading {
+        "sensor-name"?: string | null;
+        /** In degrees Celsius. */
+        temperature: number;
+    }
+"#
+        ));
+        assert!(declarations.contains(
+            "declare function read(arg1: number, arg2?: boolean | null): Promise<Flora.Reading[]>;"
+        ));
+        assert!(
+            declarations.contains("declare function raw(...args: unknown[]): Promise<unknown>;")
+        );
+        assert!(declarations.contains(
+            "declare function watch(arg1: number): AsyncIterableIterator<Flora.Reading>;"
+        ));
+        assert!(!declarations.contains("__floraInternal"));
+    }
+}
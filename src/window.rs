@@ -2,33 +2,92 @@
 //!
 //! A lot of this code comes from the webview2-rs sample, but with some modifications for our needs:
 //! https://github.com/wravery/webview2-rs/blob/main/crates/webview2-com/examples/sample.rs
-use std::{cell::RefCell, collections::HashMap, fmt, mem, ptr, rc::Rc, sync::mpsc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt, mem, ptr,
+    rc::Rc,
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Result};
 use serde::Deserialize;
-use serde_json::Value;
-use tracing::info;
+use serde_json::{json, Value};
+use tokio::{
+    runtime::{self, Runtime},
+    sync::Semaphore,
+    task::AbortHandle,
+};
+use tracing::{debug, info, warn};
 use windows::{
     core::*,
     Win32::{
-        Foundation::{COLORREF, E_POINTER, HWND, LPARAM, LRESULT, RECT, SIZE, WPARAM},
+        Foundation::{
+            COLORREF, ERROR_HOTKEY_ALREADY_REGISTERED, E_POINTER, HWND, LPARAM, LRESULT, RECT,
+            SIZE, WPARAM,
+        },
         Graphics::Gdi::CreateSolidBrush,
         System::{
             LibraryLoader::GetModuleHandleW,
             Threading::{self, GetStartupInfoW, STARTUPINFOW},
             WinRT::EventRegistrationToken,
         },
-        UI::WindowsAndMessaging::{
-            self, CreateWindowExW, RegisterClassW, ShowWindow, MSG, SW_SHOWNORMAL,
-            WINDOW_LONG_PTR_INDEX, WNDCLASSW, WS_EX_LAYERED, WS_EX_TOOLWINDOW, WS_POPUP,
-            WS_VISIBLE,
+        UI::{
+            Input::KeyboardAndMouse::ReleaseCapture,
+            WindowsAndMessaging::{
+                self, CreateWindowExW, FindWindowW, IsWindowVisible, KillTimer, PostMessageW,
+                RegisterClassW, SetLayeredWindowAttributes, SetTimer, SetWindowPos, ShowWindow,
+                HWND_BOTTOM, HWND_NOTOPMOST, HWND_TOPMOST, LWA_ALPHA, MSG, SWP_NOACTIVATE,
+                SWP_NOMOVE, SWP_NOSIZE, SWP_NOZORDER, SW_SHOWNORMAL, WINDOWPOS,
+                WINDOW_LONG_PTR_INDEX, WNDCLASSW, WS_EX_LAYERED, WS_EX_NOREDIRECTIONBITMAP,
+                WS_EX_TOOLWINDOW, WS_POPUP, WS_VISIBLE,
+            },
         },
     },
 };
 
 use webview2_com::{Microsoft::Web::WebView2::Win32::*, *};
 
-use crate::color::Color;
+use crate::{
+    animation::{Animation, Easing, Frame, Transition},
+    api, auto_size,
+    binding::{
+        self, AsyncBinding, Binding, BindingFuture, Registry, RpcError, Signature, StreamBinding,
+        StreamSender,
+    },
+    click_through::{self, ClickThrough},
+    color::{self, Color},
+    config, config_edit,
+    edit_mode::{self, DragRegion},
+    fullscreen,
+    hotkey::{self, Hotkey},
+    layer::Layer,
+    layout::Rect,
+    params::{self, Params},
+    pipe::{self, protocol::ServerResponse},
+    rpc,
+    settings::{self, Settings},
+    snap::Snap,
+    windows_api,
+};
+
+/// The timer used to poll the cursor position in [`ClickThrough::Interactive`] mode.
+const CLICK_THROUGH_TIMER_ID: usize = 1;
+/// How often the cursor position is polled in [`ClickThrough::Interactive`] mode, in milliseconds.
+const CLICK_THROUGH_POLL_INTERVAL: u32 = 50;
+/// The timer driving animations.
+const ANIMATION_TIMER_ID: usize = 2;
+/// How often animations are updated, in milliseconds.
+const ANIMATION_FRAME_INTERVAL: u32 = 15;
+/// The timer used to poll whether the cursor is over the window, when a hover opacity is set.
+const HOVER_TIMER_ID: usize = 3;
+/// How often the cursor is polled for hovering, in milliseconds.
+const HOVER_POLL_INTERVAL: u32 = 50;
+/// The timer used to poll for fullscreen applications.
+const FULLSCREEN_TIMER_ID: usize = 4;
+/// How often the foreground window is checked for being fullscreen, in milliseconds.
+const FULLSCREEN_POLL_INTERVAL: u32 = 500;
 
 #[derive(Debug)]
 pub enum Error {
@@ -84,9 +143,16 @@ struct WebViewController(ICoreWebView2Controller);
 
 pub type FloraSender = mpsc::Sender<Box<dyn FnOnce(FloraWindow) + Send>>;
 type FloraReceiver = mpsc::Receiver<Box<dyn FnOnce(FloraWindow) + Send>>;
-type BindingCallback = Box<dyn FnMut(Vec<Value>) -> Result<Value>>;
 type BindingsMap = HashMap<String, BindingCallback>;
 
+/// A bound function, which either returns its result right away, or a future that runs on the
+/// bindings runtime.
+enum BindingCallback {
+    Sync(Box<dyn FnMut(Vec<Value>) -> Result<Value>>),
+    Async(Box<dyn FnMut(Vec<Value>) -> Result<BindingFuture>>),
+    Stream(Box<dyn FnMut(Vec<Value>, StreamSender) -> Result<BindingFuture>>),
+}
+
 #[derive(Default)]
 pub struct FloraHandle(pub isize);
 
@@ -110,11 +176,59 @@ pub struct FloraWindow {
     rx: Rc<FloraReceiver>,
     pub thread_id: u32,
     bindings: Rc<RefCell<BindingsMap>>,
+    registry: Rc<RefCell<Registry>>,
+    // runs asynchronous bindings, along with the calls still running by id, and how many times the
+    // page has navigated, which tells apart the calls of the pages before
+    runtime: Rc<Runtime>,
+    pending_calls: Rc<RefCell<HashMap<u64, AbortHandle>>>,
+    navigations: Rc<RefCell<u64>>,
+    // how many more items each running stream may send to the page
+    stream_credits: Rc<RefCell<HashMap<u64, Arc<Semaphore>>>>,
     pub hwnd: Rc<HWND>,
     // either a string or url
     content: Rc<RefCell<String>>,
     // whether the content is a url
     content_url: Rc<RefCell<bool>>,
+    layer: Rc<RefCell<Layer>>,
+    click_through: Rc<RefCell<ClickThrough>>,
+    // the regions of the page that receive input in interactive click-through mode, in client
+    // coordinates
+    interactive_zones: Rc<RefCell<Vec<Rect>>>,
+    // whether the widget can be moved and resized with the mouse
+    editing: Rc<RefCell<bool>>,
+    snap: Rc<RefCell<Snap>>,
+    // the opacity of the window while the cursor is not over it, and while it is
+    opacity: Rc<RefCell<f64>>,
+    hover_opacity: Rc<RefCell<Option<f64>>>,
+    hovered: Rc<RefCell<bool>>,
+    transition: Rc<RefCell<(Transition, Duration)>>,
+    // the current opacity and offset of the window, and the animation changing them
+    frame: Rc<RefCell<Frame>>,
+    animation: Rc<RefCell<Option<RunningAnimation>>>,
+    // whether an application was fullscreen on the monitor when last checked, and whether the
+    // window was hidden because of it
+    fullscreen: Rc<RefCell<bool>>,
+    hidden_for_fullscreen: Rc<RefCell<bool>>,
+    // the registered hotkeys, where the id of each hotkey is its index plus one
+    hotkeys: Rc<RefCell<Vec<Hotkey>>>,
+    // the id of the script setting `flora.params`, which is replaced when the params change
+    params_script: Rc<RefCell<Option<String>>>,
+}
+
+/// An animation running on the window.
+#[derive(Debug, Clone, Copy)]
+struct RunningAnimation {
+    animation: Animation,
+    started: Instant,
+    // whether the window is hidden once the animation finishes
+    hide: bool,
+}
+
+impl api::Binder for FloraWindow {
+    fn bind_function<F: AsyncBinding<Args>, Args>(&mut self, name: &str, f: F) -> Result<()> {
+        self.bind_async(name, f)?;
+        Ok(())
+    }
 }
 
 impl Drop for WebViewController {
@@ -130,8 +244,24 @@ struct InvokeMessage {
     params: Vec<Value>,
 }
 
+/// A message from the page about a call it made before.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "control", rename_all = "lowercase")]
+enum ControlMessage {
+    /// The page consumed items of a stream, and has room for as many more.
+    Pull { id: u64, count: usize },
+    /// The page no longer waits for the result of the call.
+    Cancel { id: u64 },
+}
+
 impl FloraWindow {
-    fn create_window(x: i32, y: i32, width: i32, height: i32) -> Result<HWND> {
+    fn create_window(
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        background: Option<&Color>,
+    ) -> Result<HWND> {
         let class_name = w!("flora");
         let h_inst = unsafe { GetModuleHandleW(None)? };
         let mut startup_info = STARTUPINFOW {
@@ -144,15 +274,24 @@ impl FloraWindow {
             lpfnWndProc: Some(window_proc),
             hInstance: h_inst.into(),
             lpszClassName: class_name,
-            hbrBackground: unsafe { CreateSolidBrush(COLORREF(Color::Transparent.bgr())) },
+            hbrBackground: unsafe {
+                CreateSolidBrush(COLORREF(background.unwrap_or(&Color::Transparent).bgr()))
+            },
             ..Default::default()
         };
 
         unsafe { RegisterClassW(&wc) };
 
+        // without a redirection bitmap, the areas the webview leaves transparent show the desktop
+        // instead of the class background
+        let mut ex_style = WS_EX_TOOLWINDOW | WS_EX_LAYERED;
+        if background.is_some_and(|background| background.alpha() < 0xFF) {
+            ex_style |= WS_EX_NOREDIRECTIONBITMAP;
+        }
+
         let hwnd = unsafe {
             CreateWindowExW(
-                WS_EX_TOOLWINDOW | WS_EX_LAYERED,
+                ex_style,
                 class_name,
                 w!("flora"),
                 WS_POPUP | WS_VISIBLE,
@@ -167,15 +306,20 @@ impl FloraWindow {
             )?
         };
 
-        // SetLayeredWindowAttributes(hwnd, COLORREF(Color::Transparent.bgr()), 25, LWA_COLORKEY).ok();
-
         let _ = unsafe { ShowWindow(hwnd, SW_SHOWNORMAL) };
 
         Ok(hwnd)
     }
 
-    pub fn new(x: i32, y: i32, width: i32, height: i32, debug: bool) -> Result<FloraWindow> {
-        let hwnd = Self::create_window(x, y, width, height)?;
+    pub fn new(
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        background: Option<&Color>,
+        debug: bool,
+    ) -> Result<FloraWindow> {
+        let hwnd = Self::create_window(x, y, width, height, background)?;
 
         let environment = {
             let (tx, rx) = mpsc::channel();
@@ -256,16 +400,43 @@ impl FloraWindow {
             rx,
             thread_id,
             bindings: Rc::new(RefCell::new(HashMap::new())),
+            registry: Rc::new(RefCell::new(Registry::default())),
+            runtime: Rc::new(
+                runtime::Builder::new_multi_thread()
+                    .thread_name("flora-bindings")
+                    .enable_all()
+                    .build()?,
+            ),
+            pending_calls: Rc::new(RefCell::new(HashMap::new())),
+            navigations: Rc::new(RefCell::new(0)),
+            stream_credits: Rc::new(RefCell::new(HashMap::new())),
             hwnd: Rc::new(hwnd),
             content: Rc::new(RefCell::new(String::new())),
             content_url: Rc::new(RefCell::new(false)),
+            layer: Rc::new(RefCell::new(Layer::default())),
+            click_through: Rc::new(RefCell::new(ClickThrough::default())),
+            interactive_zones: Rc::new(RefCell::new(Vec::new())),
+            editing: Rc::new(RefCell::new(false)),
+            snap: Rc::new(RefCell::new(Snap::default())),
+            opacity: Rc::new(RefCell::new(1.0)),
+            hover_opacity: Rc::new(RefCell::new(None)),
+            hovered: Rc::new(RefCell::new(false)),
+            transition: Rc::new(RefCell::new((Transition::default(), Duration::ZERO))),
+            frame: Rc::new(RefCell::new(Frame::at_rest(1.0))),
+            animation: Rc::new(RefCell::new(None)),
+            fullscreen: Rc::new(RefCell::new(false)),
+            hidden_for_fullscreen: Rc::new(RefCell::new(false)),
+            hotkeys: Rc::new(RefCell::new(Vec::new())),
+            params_script: Rc::new(RefCell::new(None)),
         };
 
-        // Inject the invoke handler.
-        webview
-            .init(r#"window.external = { invoke: s => window.chrome.webview.postMessage(s) };"#)?;
+        if let Some(background) = background {
+            webview.set_background(background)?;
+        }
+
+        // Inject the invoke handler, which every binding goes through.
+        webview.init(rpc::BOOTSTRAP_SCRIPT)?;
 
-        let bindings = webview.bindings.clone();
         let bound = webview.clone();
         unsafe {
             let mut _token = EventRegistrationToken::default();
@@ -274,23 +445,8 @@ impl FloraWindow {
                     if let Some(args) = args {
                         let mut message = PWSTR(ptr::null_mut());
                         if args.WebMessageAsJson(&mut message).is_ok() {
-                            let message = CoTaskMemPWSTR::from(message);
-                            if let Ok(value) =
-                                serde_json::from_str::<InvokeMessage>(&message.to_string())
-                            {
-                                let mut bindings = bindings.borrow_mut();
-                                if let Some(f) = bindings.get_mut(&value.method) {
-                                    match (*f)(value.params) {
-                                        Ok(result) => bound.resolve(value.id, 0, result),
-                                        Err(err) => bound.resolve(
-                                            value.id,
-                                            1,
-                                            Value::String(format!("{err:#?}")),
-                                        ),
-                                    }
-                                    .unwrap();
-                                }
-                            }
+                            let message = CoTaskMemPWSTR::from(message).to_string();
+                            bound.handle_message(&message);
                         }
                     }
                     Ok(())
@@ -299,9 +455,140 @@ impl FloraWindow {
             )?;
         }
 
+        // calls still running when the page navigates away are cancelled, since the page that made
+        // them is gone
+        let bound = webview.clone();
+        unsafe {
+            let mut _token = EventRegistrationToken::default();
+            webview.webview.add_NavigationStarting(
+                &NavigationStartingEventHandler::create(Box::new(move |_webview, _args| {
+                    *bound.navigations.borrow_mut() += 1;
+                    let ids: Vec<_> = bound.pending_calls.borrow().keys().copied().collect();
+                    for id in &ids {
+                        if let Some(task) = bound.end_call(*id) {
+                            task.abort();
+                        }
+                    }
+                    if !ids.is_empty() {
+                        debug!(count = ids.len(), "cancelled binding calls");
+                    }
+                    Ok(())
+                })),
+                &mut _token,
+            )?;
+        }
+
+        // the page reports its interactive zones for the interactive click-through mode
+        let zones = webview.interactive_zones.clone();
+        webview.bind_typed(
+            click_through::INTERACTIVE_ZONES_BINDING,
+            move |reported: Vec<[f64; 4]>, device_pixel_ratio: f64| {
+                *zones.borrow_mut() = click_through::to_client_zones(&reported, device_pixel_ratio);
+                Ok(())
+            },
+        )?;
+        webview.init(click_through::INTERACTIVE_ZONES_SCRIPT)?;
+
+        // the edit mode overlay starts moving and resizing the window, and ends edit mode
+        let hwnd = webview.get_window();
+        webview.bind_typed(edit_mode::BEGIN_DRAG_BINDING, move |region: DragRegion| {
+            unsafe {
+                // the webview captures the mouse while the button is pressed
+                let _ = ReleaseCapture();
+                PostMessageW(
+                    hwnd,
+                    WindowsAndMessaging::WM_SYSCOMMAND,
+                    WPARAM(region.syscommand()),
+                    LPARAM::default(),
+                )?;
+            }
+            Ok(())
+        })?;
+        let bound = webview.clone();
+        webview.bind_typed(edit_mode::END_EDIT_MODE_BINDING, move || {
+            // leaving edit mode evaluates a script, so it can't happen while handling a message
+            // from the page
+            bound.dispatch(|webview| {
+                if let Err(e) = webview.set_edit_mode(false) {
+                    warn!("could not leave edit mode: {e}");
+                }
+            })?;
+            Ok(())
+        })?;
+        webview.init(edit_mode::EDIT_MODE_SCRIPT)?;
+
+        webview.bind_api()?;
+        webview.bind_settings()?;
+
         Ok(webview)
     }
 
+    /// Registers the bindings behind the `window.flora` API, and injects the API.
+    fn bind_api(&self) -> Result<&Self> {
+        api::bind(&mut self.clone())?;
+        self.init(api::API_SCRIPT)
+    }
+
+    /// Registers the bindings behind the settings page.
+    fn bind_settings(&self) -> Result<&Self> {
+        self.bind_typed(settings::GET_SETTINGS_BINDING, || {
+            let path = crate::CONFIG_PATH.lock().unwrap().clone();
+            Ok(Settings {
+                schema: crate::PARAM_SCHEMA.lock().unwrap().clone(),
+                params: match path {
+                    Some(path) => config::configured_params(&path)?,
+                    None => Params::new(),
+                },
+            })
+        })?;
+
+        let bound = self.clone();
+        self.bind_typed(settings::SAVE_SETTINGS_BINDING, move |params: Params| {
+            let schema = crate::PARAM_SCHEMA.lock().unwrap().clone();
+            let validated = params::validate(&schema, &params)?;
+            let path = crate::CONFIG_PATH
+                .lock()
+                .unwrap()
+                .clone()
+                .ok_or_else(|| anyhow!("the widget was not started from a config"))?;
+            // only the params set on the page are written, so the others keep following the
+            // defaults of the schema
+            config_edit::set_params(&path, &params)?;
+            info!(config = %path.display(), "saved params");
+
+            let params = validated;
+            *crate::PARAMS.lock().unwrap() = params.clone();
+            let saved = params.clone();
+            bound.dispatch(move |webview| {
+                if let Err(e) = webview.set_params(&saved) {
+                    warn!("could not update params: {e}");
+                }
+            })?;
+            Ok(params)
+        })?;
+
+        let bound = self.clone();
+        self.bind_typed(settings::CLOSE_SETTINGS_BINDING, move || {
+            bound.dispatch(|webview| {
+                if let Err(e) = webview.reload() {
+                    warn!("could not close settings: {e}");
+                }
+            })?;
+            Ok(())
+        })
+    }
+
+    /// Shows the settings page in place of the content, until it is saved or cancelled.
+    pub fn open_settings(&self) -> Result<&Self> {
+        let page = CoTaskMemPWSTR::from(settings::SETTINGS_PAGE);
+        unsafe {
+            self.webview.NavigateToString(*page.as_ref().as_pcwstr())?;
+        }
+        info!("opened settings");
+
+        Ok(self)
+    }
+
     pub fn run(self) -> Result<()> {
         let content = self.content.borrow().clone();
         let content_url = self.content_url.borrow().clone();
@@ -332,6 +619,9 @@ impl FloraWindow {
             info!(content, content_is_url = content_url, "loaded webview content");
         }
 
+        // allows window_proc to access the webview
+        FloraWindow::set_window_webview(self.get_window(), Some(Box::new(self.clone())));
+
         let mut msg = MSG::default();
         let hwnd = HWND::default();
 
@@ -389,9 +679,579 @@ impl FloraWindow {
         self
     }
 
+    /// Moves the window into the given z-order layer.
+    pub fn set_layer(&self, layer: Layer) -> Result<&Self> {
+        let hwnd = self.get_window();
+        *self.layer.borrow_mut() = layer;
+
+        // windows owned by the desktop (Progman) are not hidden when showing the desktop
+        let owner = match layer {
+            Layer::Desktop => unsafe { FindWindowW(w!("Progman"), None) }.unwrap_or_default(),
+            _ => HWND::default(),
+        };
+        unsafe { SetWindowLong(hwnd, WindowsAndMessaging::GWLP_HWNDPARENT, owner.0 as isize) };
+
+        let insert_after = match layer {
+            Layer::Topmost => HWND_TOPMOST,
+            Layer::Normal => HWND_NOTOPMOST,
+            Layer::Bottom | Layer::Desktop => HWND_BOTTOM,
+        };
+        unsafe {
+            SetWindowPos(
+                hwnd,
+                insert_after,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+            )?;
+        }
+
+        Ok(self)
+    }
+
+    /// Sets the background color of the webview. Fully opaque colors are drawn by the webview
+    /// itself; otherwise the webview is made transparent, and any remaining alpha is applied to the
+    /// root element of each page, since the webview only supports opaque or fully transparent
+    /// default backgrounds.
+    pub fn set_background(&self, background: &Color) -> Result<&Self> {
+        let argb = background.argb();
+        let (r, g, b) = ((argb >> 16) as u8, (argb >> 8) as u8, argb as u8);
+        let alpha = background.alpha();
+
+        let color = match alpha {
+            0xFF => COREWEBVIEW2_COLOR {
+                A: 0xFF,
+                R: r,
+                G: g,
+                B: b,
+            },
+            _ => COREWEBVIEW2_COLOR::default(),
+        };
+        unsafe {
+            let controller: ICoreWebView2Controller2 = self.controller.0.cast()?;
+            controller.SetDefaultBackgroundColor(color)?;
+        }
+
+        if alpha > 0 && alpha < 0xFF {
+            // a zero specificity rule, so any background set by the content takes precedence
+            let css = serde_json::to_string(&format!(
+                ":where(html) {{ background-color: rgba({r}, {g}, {b}, {}); }}",
+                alpha as f64 / 255.0
+            ))?;
+            self.init(&format!(
+                r#"document.addEventListener("DOMContentLoaded", () => {{
+                    const style = document.createElement("style");
+                    style.textContent = {css};
+                    document.head.prepend(style);
+                }});"#
+            ))?;
+        }
+
+        Ok(self)
+    }
+
+    /// Sets the alpha value applied to the whole window, where 0 is invisible and 0xFF is opaque.
+    /// While the cursor is over the window, the hover opacity applies instead, if set.
+    pub fn set_opacity(&self, alpha: u8) -> Result<&Self> {
+        *self.opacity.borrow_mut() = alpha as f64 / 255.0;
+        if self.animation.borrow().is_none() {
+            self.apply_frame(Frame::at_rest(self.target_opacity()))?;
+        }
+
+        Ok(self)
+    }
+
+    /// Sets the opacity of the window while the cursor is over it, from 0.0 to 1.0. The window
+    /// fades between its opacity and the hover opacity as the cursor enters and leaves it.
+    pub fn set_hover_opacity(&self, opacity: Option<f64>) -> Result<&Self> {
+        let hwnd = self.get_window();
+        *self.hover_opacity.borrow_mut() = opacity.map(|opacity| opacity.clamp(0.0, 1.0));
+
+        match opacity {
+            Some(_) => {
+                if unsafe { SetTimer(hwnd, HOVER_TIMER_ID, HOVER_POLL_INTERVAL, None) } == 0 {
+                    return Err(windows::core::Error::from_win32().into());
+                }
+            }
+            None => {
+                let _ = unsafe { KillTimer(hwnd, HOVER_TIMER_ID) };
+                self.hovered.replace(false);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Registers global hotkeys for the window, replacing the ones registered before. Hotkeys that
+    /// cannot be registered, e.g. because another application already uses them, are reported
+    /// and skipped.
+    pub fn set_hotkeys(&self, hotkeys: &[Hotkey]) -> &Self {
+        let hwnd = self.get_window();
+        for id in 1..=self.hotkeys.borrow().len() {
+            windows_api::unregister_hotkey(hwnd, id as i32);
+        }
+
+        for chord in hotkey::duplicates(hotkeys) {
+            warn!(%chord, "hotkey is bound more than once, only the first binding is used");
+        }
+
+        for (i, Hotkey(chord, action)) in hotkeys.iter().enumerate() {
+            if hotkeys[..i].iter().any(|Hotkey(other, _)| other == chord) {
+                continue;
+            }
+
+            match windows_api::register_hotkey(hwnd, i as i32 + 1, chord) {
+                Ok(()) => info!(%chord, ?action, "registered hotkey"),
+                Err(e) if e.code() == ERROR_HOTKEY_ALREADY_REGISTERED.to_hresult() => warn!(
+                    %chord,
+                    ?action,
+                    "could not register hotkey, it is already used by another application or widget"
+                ),
+                Err(e) => warn!(%chord, ?action, "could not register hotkey: {e}"),
+            }
+        }
+        *self.hotkeys.borrow_mut() = hotkeys.to_vec();
+
+        self
+    }
+
+    /// Performs the action of the hotkey with the given id, the same way the pipe server would.
+    fn handle_hotkey(&self, id: usize) {
+        let Some(Hotkey(chord, action)) = id
+            .checked_sub(1)
+            .and_then(|i| self.hotkeys.borrow().get(i).copied())
+        else {
+            return;
+        };
+
+        info!(%chord, ?action, "hotkey pressed");
+        if let ServerResponse::Err(e) = pipe::server::handle_request(action.request()) {
+            warn!(%chord, ?action, "could not perform hotkey action: {e}");
+        }
+    }
+
+    /// Sets how the window is shown and hidden, and how long transitions take.
+    pub fn set_transition(&self, transition: Transition, duration: Duration) -> &Self {
+        *self.transition.borrow_mut() = (transition, duration);
+        self
+    }
+
+    /// Sets whether the window hides itself while an application is fullscreen on its monitor.
+    pub fn set_hide_on_fullscreen(&self, enabled: bool) -> Result<&Self> {
+        let hwnd = self.get_window();
+
+        match enabled {
+            true => {
+                if unsafe { SetTimer(hwnd, FULLSCREEN_TIMER_ID, FULLSCREEN_POLL_INTERVAL, None) }
+                    == 0
+                {
+                    return Err(windows::core::Error::from_win32().into());
+                }
+            }
+            false => {
+                let _ = unsafe { KillTimer(hwnd, FULLSCREEN_TIMER_ID) };
+                self.fullscreen.replace(false);
+                if self.hidden_for_fullscreen.replace(false) {
+                    self.show()?;
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Hides the window when an application goes fullscreen on its monitor, and shows it again
+    /// once it leaves fullscreen. The page is notified with a `flora:fullscreen` event either way.
+    fn update_fullscreen(&self) -> Result<()> {
+        let (monitor, _dpi) = crate::get_target_monitor();
+        let fullscreen = windows_api::get_foreground_window()
+            .is_some_and(|window| fullscreen::is_fullscreen(&window, &monitor));
+        if self.fullscreen.replace(fullscreen) == fullscreen {
+            return Ok(());
+        }
+        info!(fullscreen, "fullscreen application changed");
+
+        // the window is only shown again if it was hidden for the fullscreen application, and
+        // not in the meantime
+        let visible = unsafe { IsWindowVisible(self.get_window()) }.as_bool();
+        if fullscreen && visible {
+            self.hide()?;
+            self.hidden_for_fullscreen.replace(true);
+        } else if !fullscreen && *self.hidden_for_fullscreen.borrow() {
+            self.show()?;
+        }
+
+        self.emit(
+            "fullscreen",
+            serde_json::json!({ "fullscreen": fullscreen }),
+        )?;
+
+        Ok(())
+    }
+
+    /// Dispatches a `flora:<name>` event on the window object of the page, with `detail` as the
+    /// detail of the event.
+    pub fn emit(&self, name: &str, detail: Value) -> Result<&Self> {
+        let event = format!("flora:{name}");
+        self.eval(&format!(
+            "window.dispatchEvent(new CustomEvent({}, {{ detail: {detail} }}));",
+            Value::String(event)
+        ))
+    }
+
+    /// Shows the window, animating it in with the configured transition.
+    pub fn show(&self) -> Result<&Self> {
+        let hwnd = self.get_window();
+        self.hidden_for_fullscreen.replace(false);
+        let (transition, _) = *self.transition.borrow();
+        let target = Frame::at_rest(self.target_opacity());
+
+        if transition == Transition::None {
+            self.stop_animation();
+            self.apply_frame(target)?;
+            windows_api::show_window(hwnd);
+            return Ok(self);
+        }
+
+        // a window that is still being hidden is animated back in from where it is
+        if !unsafe { IsWindowVisible(hwnd) }.as_bool() {
+            let (monitor, _dpi) = crate::get_target_monitor();
+            self.apply_frame(transition.hidden_frame(&crate::get_window_rect(), &monitor))?;
+            windows_api::show_window(hwnd);
+        }
+        self.animate(target, Easing::Out, false)
+    }
+
+    /// Hides the window, animating it out with the configured transition.
+    pub fn hide(&self) -> Result<&Self> {
+        let hwnd = self.get_window();
+        self.hidden_for_fullscreen.replace(false);
+        let (transition, _) = *self.transition.borrow();
+
+        if transition == Transition::None || !unsafe { IsWindowVisible(hwnd) }.as_bool() {
+            self.stop_animation();
+            windows_api::hide_window(hwnd);
+            return Ok(self);
+        }
+
+        let (monitor, _dpi) = crate::get_target_monitor();
+        let hidden = transition.hidden_frame(&crate::get_window_rect(), &monitor);
+        self.animate(hidden, Easing::In, true)
+    }
+
+    /// Closes the window, which stops the widget once the message loop gets to it.
+    pub fn close(&self) -> Result<()> {
+        unsafe {
+            PostMessageW(
+                self.get_window(),
+                WindowsAndMessaging::WM_CLOSE,
+                WPARAM(0),
+                LPARAM(0),
+            )?
+        };
+        Ok(())
+    }
+
+    /// Hides the window if it is visible, and shows it otherwise. A window that is being hidden is
+    /// shown again.
+    pub fn toggle(&self) -> Result<&Self> {
+        let visible = unsafe { IsWindowVisible(self.get_window()) }.as_bool();
+        let hiding = self.animation.borrow().is_some_and(|running| running.hide);
+
+        match visible && !hiding {
+            true => self.hide(),
+            false => self.show(),
+        }
+    }
+
+    /// Loads the content of the window again.
+    pub fn reload(&self) -> Result<&Self> {
+        let content = CoTaskMemPWSTR::from(self.content.borrow().as_str());
+        let content = *content.as_ref().as_pcwstr();
+        unsafe {
+            match *self.content_url.borrow() {
+                true => self.webview.Navigate(content)?,
+                false => self.webview.NavigateToString(content)?,
+            }
+        }
+        info!("reloaded webview content");
+
+        Ok(self)
+    }
+
+    /// The opacity the window should be at, depending on whether the cursor is over it.
+    fn target_opacity(&self) -> f64 {
+        match *self.hover_opacity.borrow() {
+            Some(opacity) if *self.hovered.borrow() => opacity,
+            _ => *self.opacity.borrow(),
+        }
+    }
+
+    /// Starts animating the window from its current frame to `to`, replacing any running
+    /// animation.
+    fn animate(&self, to: Frame, easing: Easing, hide: bool) -> Result<&Self> {
+        let (_, duration) = *self.transition.borrow();
+        *self.animation.borrow_mut() = Some(RunningAnimation {
+            animation: Animation {
+                from: *self.frame.borrow(),
+                to,
+                duration,
+                easing,
+            },
+            started: Instant::now(),
+            hide,
+        });
+
+        if unsafe {
+            SetTimer(
+                self.get_window(),
+                ANIMATION_TIMER_ID,
+                ANIMATION_FRAME_INTERVAL,
+                None,
+            )
+        } == 0
+        {
+            return Err(windows::core::Error::from_win32().into());
+        }
+
+        Ok(self)
+    }
+
+    fn stop_animation(&self) {
+        self.animation.take();
+        let _ = unsafe { KillTimer(self.get_window(), ANIMATION_TIMER_ID) };
+    }
+
+    /// Moves the running animation to its next frame, hiding the window if it finished hiding it.
+    fn step_animation(&self) -> Result<()> {
+        let Some(running) = *self.animation.borrow() else {
+            self.stop_animation();
+            return Ok(());
+        };
+
+        let elapsed = running.started.elapsed();
+        self.apply_frame(running.animation.frame_at(elapsed))?;
+
+        if running.animation.is_finished(elapsed) {
+            self.stop_animation();
+            if running.hide {
+                windows_api::hide_window(self.get_window());
+                // the window is put back in place, ready to be shown again
+                self.apply_frame(Frame::at_rest(0.0))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the opacity of the window, and offsets it from where it is laid out.
+    fn apply_frame(&self, frame: Frame) -> Result<()> {
+        let hwnd = self.get_window();
+        let previous = self.frame.replace(frame);
+
+        let alpha = color::opacity_to_alpha(frame.opacity);
+        unsafe { SetLayeredWindowAttributes(hwnd, COLORREF(0), alpha, LWA_ALPHA)? };
+
+        if frame.pixel_offset() != previous.pixel_offset() {
+            windows_api::set_window_rect(hwnd, &self.offset_rect(crate::get_window_rect()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Offsets the rect the window is laid out at by the current frame.
+    fn offset_rect(&self, rect: Rect) -> Rect {
+        let (x, y) = self.frame.borrow().pixel_offset();
+        Rect {
+            x: rect.x + x,
+            y: rect.y + y,
+            ..rect
+        }
+    }
+
+    /// Fades the window to its hover opacity when the cursor enters it, and back when it leaves.
+    fn update_hover(&self) {
+        let hwnd = self.get_window();
+        let hiding = self.animation.borrow().is_some_and(|running| running.hide);
+        if hiding || !unsafe { IsWindowVisible(hwnd) }.as_bool() {
+            return;
+        }
+
+        let hovered = match (
+            windows_api::get_client_cursor_pos(hwnd),
+            windows_api::get_window_bounds(hwnd),
+        ) {
+            (Ok((x, y)), Ok(bounds)) => Rect {
+                x: 0,
+                y: 0,
+                ..bounds
+            }
+            .contains(x, y),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!("could not get cursor position: {e}");
+                return;
+            }
+        };
+
+        if self.hovered.replace(hovered) != hovered {
+            let target = Frame::at_rest(self.target_opacity());
+            if let Err(e) = self.animate(target, Easing::InOut, false) {
+                warn!("could not animate hover opacity: {e}");
+            }
+        }
+    }
+
+    /// Sets whether mouse input passes through the window.
+    pub fn set_click_through(&self, mode: ClickThrough) -> Result<&Self> {
+        let hwnd = self.get_window();
+        *self.click_through.borrow_mut() = mode;
+
+        match mode {
+            ClickThrough::Interactive => {
+                if unsafe {
+                    SetTimer(
+                        hwnd,
+                        CLICK_THROUGH_TIMER_ID,
+                        CLICK_THROUGH_POLL_INTERVAL,
+                        None,
+                    )
+                } == 0
+                {
+                    return Err(windows::core::Error::from_win32().into());
+                }
+            }
+            _ => {
+                let _ = unsafe { KillTimer(hwnd, CLICK_THROUGH_TIMER_ID) };
+            }
+        }
+        // in interactive mode, input is let through until the cursor is known to be over a zone.
+        // In edit mode, the window always receives input
+        let editing = *self.editing.borrow();
+        windows_api::set_input_transparent(hwnd, !editing && mode != ClickThrough::Off);
+
+        Ok(self)
+    }
+
+    /// Sets how the window snaps while it is moved or resized in edit mode.
+    pub fn set_snap(&self, snap: Snap) -> &Self {
+        *self.snap.borrow_mut() = snap;
+        self
+    }
+
+    /// Sizes the width and height of the window to the content of the page, if enabled. This only
+    /// applies to pages loaded afterwards.
+    pub fn set_auto_size(&self, (width, height): (bool, bool)) -> Result<&Self> {
+        if !width && !height {
+            return Ok(self);
+        }
+
+        let bound = self.clone();
+        self.bind_typed(
+            auto_size::CONTENT_SIZE_BINDING,
+            move |width: f64, height: f64, device_pixel_ratio: f64| {
+                // resizing the window resizes the webview, which can't happen while handling a
+                // message from the page
+                bound.dispatch(move |webview| {
+                    webview.resize_to_content(width, height, device_pixel_ratio)
+                })?;
+                Ok(())
+            },
+        )?;
+        self.init(&auto_size::auto_size_script(width))?;
+
+        Ok(self)
+    }
+
+    /// Resizes the window to the measured size of the page, in CSS pixels. The window is left alone
+    /// in edit mode, and follows the content again once it is moved or the content changes.
+    fn resize_to_content(&self, width: f64, height: f64, device_pixel_ratio: f64) {
+        let rect = self.offset_rect(crate::set_content_size(width, height, device_pixel_ratio));
+        if *self.editing.borrow() {
+            return;
+        }
+
+        let hwnd = self.get_window();
+        if windows_api::get_window_bounds(hwnd).is_ok_and(|bounds| bounds == rect) {
+            return;
+        }
+        match windows_api::set_window_rect(hwnd, &rect) {
+            Ok(()) => info!(
+                width = rect.width,
+                height = rect.height,
+                "resized to content"
+            ),
+            Err(e) => warn!("could not resize window to content: {e}"),
+        }
+    }
+
+    /// Moves and resizes the window to `rect` for as long as the widget runs, without changing
+    /// its config. Returns the rect the window is placed at, which differs from `rect` where the
+    /// window is sized to its content.
+    pub fn place(&self, rect: Rect) -> Result<Rect> {
+        let rect = crate::place_window(&rect);
+        windows_api::set_window_rect(self.get_window(), &self.offset_rect(rect))?;
+        info!(
+            x = rect.x,
+            y = rect.y,
+            width = rect.width,
+            height = rect.height,
+            "placed window"
+        );
+
+        Ok(rect)
+    }
+
+    /// Enters or leaves edit mode.
+    pub fn toggle_edit_mode(&self) -> Result<&Self> {
+        let editing = *self.editing.borrow();
+        self.set_edit_mode(!editing)
+    }
+
+    /// Enters or leaves edit mode, where the window can be moved and resized with the mouse. When
+    /// leaving edit mode, the bounds of the window are saved into the config.
+    pub fn set_edit_mode(&self, editing: bool) -> Result<&Self> {
+        let hwnd = self.get_window();
+        let was_editing = self.editing.replace(editing);
+
+        let mode = *self.click_through.borrow();
+        windows_api::set_input_transparent(hwnd, !editing && mode != ClickThrough::Off);
+        self.eval(&format!(
+            "window.__floraSetEditMode && window.__floraSetEditMode({editing});"
+        ))?;
+
+        if was_editing && !editing {
+            crate::save_window_rect(&windows_api::get_window_bounds(hwnd)?)?;
+        }
+        info!(editing, "set edit mode");
+
+        Ok(self)
+    }
+
+    /// Lets input through unless the cursor is over one of the interactive zones of the page.
+    fn update_click_through(&self) {
+        let hwnd = self.get_window();
+        match windows_api::get_client_cursor_pos(hwnd) {
+            Ok((x, y)) => {
+                let interactive = click_through::hit_test(&self.interactive_zones.borrow(), x, y);
+                windows_api::set_input_transparent(hwnd, !interactive);
+            }
+            Err(e) => warn!("could not get cursor position: {e}"),
+        }
+    }
+
     pub fn init(&self, js: &str) -> Result<&Self> {
+        self.add_script(js)?;
+        Ok(self)
+    }
+
+    /// Adds a script that runs whenever a document is created, returning the id that removes it.
+    fn add_script(&self, js: &str) -> Result<String> {
         let webview = self.webview.clone();
         let js = String::from(js);
+        let id = Rc::new(RefCell::new(String::new()));
+        let script_id = id.clone();
         AddScriptToExecuteOnDocumentCreatedCompletedHandler::wait_for_async_operation(
             Box::new(move |handler| unsafe {
                 let js = CoTaskMemPWSTR::from(js.as_str());
@@ -399,10 +1259,22 @@ impl FloraWindow {
                     .AddScriptToExecuteOnDocumentCreated(*js.as_ref().as_pcwstr(), &handler)
                     .map_err(webview2_com::Error::WindowsError)
             }),
-            Box::new(|error_code, _id| error_code),
+            Box::new(move |error_code, id| {
+                script_id.replace(id);
+                error_code
+            }),
         )
         .map_err(|e| anyhow!("could not add script: {e:#?}"))?;
 
+        Ok(id.take())
+    }
+
+    fn remove_script(&self, id: &str) -> Result<&Self> {
+        let id = CoTaskMemPWSTR::from(id);
+        unsafe {
+            self.webview
+                .RemoveScriptToExecuteOnDocumentCreated(*id.as_ref().as_pcwstr())?;
+        }
         Ok(self)
     }
 
@@ -439,55 +1311,271 @@ impl FloraWindow {
         Ok(self)
     }
 
+    /// Binds a function taking the raw JSON arguments of the call. Prefer [`Self::bind_typed`],
+    /// which decodes the arguments and records the signature of the binding.
     pub fn bind<F>(&self, name: &str, f: F) -> Result<&Self>
     where
         F: FnMut(Vec<Value>) -> Result<Value> + 'static,
     {
-        self.bindings
+        rpc::validate_name(name)?;
+        let signature = self
+            .registry
+            .borrow_mut()
+            .register_untyped(name)
+            .to_string();
+        debug!(signature, "bound function");
+        self.bind_callback(name, BindingCallback::Sync(Box::new(f)))
+    }
+
+    /// Binds a function with typed arguments and return value, which are decoded from and encoded
+    /// into JSON. Arguments that can't be decoded reject the call with an error naming the
+    /// argument.
+    pub fn bind_typed<F, Args>(&self, name: &str, mut f: F) -> Result<&Self>
+    where
+        F: Binding<Args>,
+    {
+        rpc::validate_name(name)?;
+        let signature = self
+            .registry
+            .borrow_mut()
+            .register::<F, Args>(name)
+            .to_string();
+        debug!(signature, "bound function");
+        let binding = String::from(name);
+        self.bind_callback(
+            name,
+            BindingCallback::Sync(Box::new(move |params| f.call(&binding, params))),
+        )
+    }
+
+    /// Binds an asynchronous function with typed arguments and return value. The function runs on
+    /// a Tokio runtime instead of the window thread, and calls still running when the page
+    /// navigates away are cancelled.
+    pub fn bind_async<F, Args>(&self, name: &str, mut f: F) -> Result<&Self>
+    where
+        F: AsyncBinding<Args>,
+    {
+        rpc::validate_name(name)?;
+        let signature = self
+            .registry
+            .borrow_mut()
+            .register_async::<F, Args>(name)
+            .to_string();
+        debug!(signature, "bound function");
+        let binding = String::from(name);
+        self.bind_callback(
+            name,
+            BindingCallback::Async(Box::new(move |params| f.call(&binding, params))),
+        )
+    }
+
+    /// Binds a streaming function with typed arguments, which sends items to the page through the
+    /// [`binding::Sink`] passed as its last argument. The page reads the items with an async
+    /// iterator, and the call is cancelled when the page stops reading or navigates away.
+    pub fn bind_stream<F, Args, T>(&self, name: &str, mut f: F) -> Result<&Self>
+    where
+        F: StreamBinding<Args, T>,
+    {
+        rpc::validate_name(name)?;
+        let signature = self
+            .registry
             .borrow_mut()
-            .insert(String::from(name), Box::new(f));
-
-        let js = String::from(
-            r#"
-            (function() {
-                var name = '"#,
-        ) + name
-            + r#"';
-                var RPC = window._rpc = (window._rpc || {nextSeq: 1});
-                window[name] = function() {
-                    var seq = RPC.nextSeq++;
-                    var promise = new Promise(function(resolve, reject) {
-                        RPC[seq] = {
-                            resolve: resolve,
-                            reject: reject,
-                        };
-                    });
-                    window.external.invoke({
-                        id: seq,
-                        method: name,
-                        params: Array.prototype.slice.call(arguments),
-                    });
-                    return promise;
+            .register_stream::<F, Args, T>(name)
+            .to_string();
+        debug!(signature, "bound function");
+        let binding = String::from(name);
+        self.bind_callback(
+            name,
+            BindingCallback::Stream(Box::new(move |params, sender| {
+                f.call(&binding, params, sender)
+            })),
+        )
+    }
+
+    /// Handles a message posted by the page, which either calls a binding or controls a call made
+    /// before.
+    fn handle_message(&self, message: &str) {
+        if let Ok(control) = serde_json::from_str::<ControlMessage>(message) {
+            self.control_call(control);
+            return;
+        }
+
+        let call = match serde_json::from_str::<InvokeMessage>(message) {
+            Ok(call) => call,
+            // a message with an id is still answered, so the call doesn't hang
+            Err(e) => {
+                match serde_json::from_str::<Value>(message)
+                    .ok()
+                    .and_then(|message| message.get("id")?.as_u64())
+                {
+                    Some(id) => {
+                        let err = RpcError::new(
+                            RpcError::INVALID_REQUEST,
+                            format!("invalid call: {e}"),
+                            None,
+                        );
+                        self.reply(id, Err(err.into())).unwrap();
+                    }
+                    None => warn!("ignoring invalid message from the page: {e}"),
                 }
-            })()"#;
+                return;
+            }
+        };
 
-        self.init(&js)
+        let mut bindings = self.bindings.borrow_mut();
+        let future = match bindings.get_mut(&call.method) {
+            Some(BindingCallback::Sync(f)) => {
+                let result = f(call.params);
+                self.reply(call.id, result).unwrap();
+                return;
+            }
+            Some(BindingCallback::Async(f)) => f(call.params),
+            Some(BindingCallback::Stream(f)) => {
+                let credit = Arc::new(Semaphore::new(binding::STREAM_CAPACITY));
+                let sender = self.stream_sender(call.id, credit.clone());
+                f(call.params, sender).inspect(|_| {
+                    self.stream_credits.borrow_mut().insert(call.id, credit);
+                })
+            }
+            None => Err(RpcError::new(
+                RpcError::METHOD_NOT_FOUND,
+                format!("`{}` is not a bound function", call.method),
+                Some(json!({ "method": call.method })),
+            )
+            .into()),
+        };
+
+        match future {
+            Ok(future) => self.spawn_call(call.id, future),
+            Err(err) => {
+                self.reply(call.id, Err(err)).unwrap();
+            }
+        }
     }
 
-    pub fn resolve(&self, id: u64, status: i32, result: Value) -> Result<&Self> {
-        let result = result.to_string();
+    fn control_call(&self, message: ControlMessage) {
+        match message {
+            ControlMessage::Pull { id, count } => {
+                if let Some(credit) = self.stream_credits.borrow().get(&id) {
+                    binding::add_credit(credit, count);
+                }
+            }
+            ControlMessage::Cancel { id } => {
+                if let Some(task) = self.end_call(id) {
+                    task.abort();
+                    debug!(id, "cancelled binding call");
+                }
+            }
+        }
+    }
 
-        self.dispatch(move |webview| {
-            let method = match status {
-                0 => "resolve",
-                _ => "reject",
+    /// Returns the sender passing the items of the streaming call with the given id to the page.
+    /// Items of a call that has ended are dropped.
+    fn stream_sender(&self, id: u64, credit: Arc<Semaphore>) -> StreamSender {
+        let tx = self.tx.clone();
+        let thread_id = self.thread_id;
+        let navigation = *self.navigations.borrow();
+
+        StreamSender::new(credit, move |item| {
+            let push = move |webview: FloraWindow| {
+                if !webview.is_running(id, navigation) {
+                    return;
+                }
+                if let Err(e) = webview.eval(&rpc::push_script(id, &item)) {
+                    warn!("could not send stream item: {e}");
+                }
+            };
+            tx.send(Box::new(push))
+                .map_err(|_| anyhow!("the window is closed"))?;
+            windows_api::send_app_message(thread_id)
+        })
+    }
+
+    /// Returns whether the call with the given id, made by the page after `navigation`
+    /// navigations, is still running.
+    fn is_running(&self, id: u64, navigation: u64) -> bool {
+        *self.navigations.borrow() == navigation && self.pending_calls.borrow().contains_key(&id)
+    }
+
+    /// Forgets about a running call, closing its stream if it has one, and returns its task.
+    fn end_call(&self, id: u64) -> Option<AbortHandle> {
+        if let Some(credit) = self.stream_credits.borrow_mut().remove(&id) {
+            credit.close();
+        }
+        self.pending_calls.borrow_mut().remove(&id)
+    }
+
+    /// Runs the future of an asynchronous binding call on the bindings runtime. The call is
+    /// resolved on the window thread once the future completes, unless the page navigated away
+    /// in the meantime.
+    fn spawn_call(&self, id: u64, future: BindingFuture) {
+        let tx = self.tx.clone();
+        let thread_id = self.thread_id;
+        let navigation = *self.navigations.borrow();
+
+        let task = self.runtime.spawn(async move {
+            let result = future.await;
+            let finish = move |webview: FloraWindow| {
+                if !webview.is_running(id, navigation) {
+                    return;
+                }
+                webview.end_call(id);
+                if let Err(e) = webview.reply(id, result) {
+                    warn!("could not resolve binding call: {e}");
+                }
             };
-            let js = format!(
-                r#"
-                window._rpc[{id}].{method}({result});
-                window._rpc[{id}] = undefined;"#
-            );
+            if tx.send(Box::new(finish)).is_ok() {
+                let _ = windows_api::send_app_message(thread_id);
+            }
+        });
+        self.pending_calls
+            .borrow_mut()
+            .insert(id, task.abort_handle());
+    }
+
+    /// Resolves the call with the result of the binding, or rejects it with the error as an
+    /// [`RpcError`].
+    fn reply(&self, id: u64, result: Result<Value>) -> Result<&Self> {
+        match result {
+            Ok(result) => self.resolve(id, 0, result),
+            Err(err) => self.resolve(id, 1, serde_json::to_value(RpcError::from(err))?),
+        }
+    }
+
+    /// Sets how long calls to bindings wait for a result before they are rejected, in
+    /// milliseconds. 0 waits forever. The page can change it with `window._rpc.timeout`.
+    pub fn set_rpc_timeout(&self, timeout: u32) -> Result<&Self> {
+        self.init(&rpc::timeout_script(timeout))
+    }
 
+    /// Sets `flora.params`, which pages see before their own scripts run. When the params change,
+    /// the current page gets them right away, along with a `flora:params` event.
+    pub fn set_params(&self, params: &Params) -> Result<&Self> {
+        let js = params::params_script(params);
+        let id = self.add_script(&js)?;
+        let Some(previous) = self.params_script.replace(Some(id)) else {
+            return Ok(self);
+        };
+
+        self.remove_script(&previous)?;
+        self.eval(&js)?;
+        self.emit("params", Value::Object(params.clone()))
+    }
+
+    /// Returns the signatures of all bindings, ordered by name.
+    pub fn signatures(&self) -> Vec<Signature> {
+        self.registry.borrow().signatures().cloned().collect()
+    }
+
+    fn bind_callback(&self, name: &str, f: BindingCallback) -> Result<&Self> {
+        let stream = matches!(f, BindingCallback::Stream(_));
+        self.bindings.borrow_mut().insert(String::from(name), f);
+        self.init(&rpc::bind_script(&[name], stream))
+    }
+
+    pub fn resolve(&self, id: u64, status: i32, result: Value) -> Result<&Self> {
+        self.dispatch(move |webview| {
+            let js = rpc::settle_script(id, status == 0, &result);
             webview.eval(&js).expect("eval return script");
         })
     }
@@ -559,6 +1647,88 @@ pub extern "system" fn window_proc(
             LRESULT::default()
         }
 
+        WindowsAndMessaging::WM_WINDOWPOSCHANGING => {
+            // keep bottom and desktop widgets below other windows, e.g. when they are clicked
+            if webview.layer.borrow().is_pinned_to_bottom() {
+                let pos = unsafe { &mut *(l_param.0 as *mut WINDOWPOS) };
+                if !pos.flags.contains(SWP_NOZORDER) {
+                    pos.hwndInsertAfter = HWND_BOTTOM;
+                }
+            }
+            unsafe { WindowsAndMessaging::DefWindowProcW(hwnd, msg, w_param, l_param) }
+        }
+
+        WindowsAndMessaging::WM_TIMER if w_param.0 == CLICK_THROUGH_TIMER_ID => {
+            if *webview.click_through.borrow() == ClickThrough::Interactive
+                && !*webview.editing.borrow()
+            {
+                webview.update_click_through();
+            }
+            LRESULT::default()
+        }
+
+        WindowsAndMessaging::WM_TIMER if w_param.0 == ANIMATION_TIMER_ID => {
+            if let Err(e) = webview.step_animation() {
+                warn!("could not animate window: {e}");
+                webview.stop_animation();
+            }
+            LRESULT::default()
+        }
+
+        WindowsAndMessaging::WM_TIMER if w_param.0 == FULLSCREEN_TIMER_ID => {
+            if let Err(e) = webview.update_fullscreen() {
+                warn!("could not update window for fullscreen application: {e}");
+            }
+            LRESULT::default()
+        }
+
+        WindowsAndMessaging::WM_TIMER if w_param.0 == HOVER_TIMER_ID => {
+            webview.update_hover();
+            LRESULT::default()
+        }
+
+        WindowsAndMessaging::WM_HOTKEY => {
+            webview.handle_hotkey(w_param.0);
+            LRESULT::default()
+        }
+
+        WindowsAndMessaging::WM_MOVING | WindowsAndMessaging::WM_SIZING
+            if *webview.editing.borrow() =>
+        {
+            let bounds = unsafe { &mut *(l_param.0 as *mut RECT) };
+            let rect = Rect {
+                x: bounds.left,
+                y: bounds.top,
+                width: bounds.right - bounds.left,
+                height: bounds.bottom - bounds.top,
+            };
+
+            let (monitor, _dpi) = crate::get_target_monitor();
+            let snap = *webview.snap.borrow();
+            let snapped = match msg {
+                WindowsAndMessaging::WM_MOVING => snap.snap_move(&rect, &monitor),
+                _ => snap.snap_resize(&rect, edit_mode::sizing_edges(w_param.0), &monitor),
+            };
+
+            *bounds = RECT {
+                left: snapped.x,
+                top: snapped.y,
+                right: snapped.x + snapped.width,
+                bottom: snapped.y + snapped.height,
+            };
+            LRESULT(1) // TRUE
+        }
+
+        WindowsAndMessaging::WM_DISPLAYCHANGE | WindowsAndMessaging::WM_DPICHANGED => {
+            // monitors may have been added, removed or rearranged, so the target monitor is
+            // selected again, and a content size is converted for its DPI
+            let rect = crate::get_window_rect();
+            if let Err(e) = windows_api::set_window_rect(hwnd, &rect) {
+                warn!("could not update window bounds after display change: {e}");
+            }
+            LRESULT::default()
+        }
+
         WindowsAndMessaging::WM_CLOSE => {
             unsafe {
                 let _ = WindowsAndMessaging::DestroyWindow(hwnd);
@@ -1,7 +1,31 @@
+use std::mem;
+
 use anyhow::Result;
 use windows::Win32::{
-    Foundation::{HWND, LPARAM, WPARAM},
-    UI::WindowsAndMessaging::{self, ShowWindow, SW_HIDE, SW_SHOWNORMAL},
+    Foundation::{BOOL, HWND, LPARAM, POINT, RECT, WPARAM},
+    Graphics::Gdi::{
+        EnumDisplayMonitors, GetMonitorInfoW, ScreenToClient, HDC, HMONITOR, MONITORINFO,
+        MONITORINFOEXW,
+    },
+    UI::{
+        HiDpi::{GetDpiForMonitor, GetDpiForWindow, MDT_EFFECTIVE_DPI},
+        Input::KeyboardAndMouse::{
+            RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_NOREPEAT,
+        },
+        WindowsAndMessaging::{
+            self, GetClassNameW, GetCursorPos, GetForegroundWindow, GetWindowLongW, GetWindowRect,
+            IsZoomed, SetWindowLongW, SetWindowPos, ShowWindow, GWL_EXSTYLE, MONITORINFOF_PRIMARY,
+            SWP_NOACTIVATE, SWP_NOZORDER, SW_HIDE, SW_SHOWNORMAL, WS_EX_TRANSPARENT,
+        },
+    },
+};
+
+use crate::{
+    dpi::DEFAULT_DPI,
+    fullscreen::ForegroundWindow,
+    hotkey::Chord,
+    layout::Rect,
+    monitor::{self, Monitor},
 };
 
 pub fn show_window(hwnd: HWND) -> bool {
@@ -22,3 +46,167 @@ pub fn send_app_message(thread_id: u32) -> Result<()> {
         )?)
     }
 }
+
+/// Returns all connected monitors, ordered as described in [`monitor::sort`].
+pub fn get_monitors() -> Vec<Monitor> {
+    // the vector will get filled with monitors by the callback
+    let monitors_ptr = Box::into_raw(Box::new(Vec::new()));
+
+    let _ = unsafe {
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(enum_monitors_callback),
+            LPARAM(monitors_ptr as isize),
+        )
+    };
+
+    let mut monitors = unsafe { *Box::from_raw(monitors_ptr) };
+    monitor::sort(&mut monitors);
+    monitors
+}
+
+unsafe extern "system" fn enum_monitors_callback(
+    hmonitor: HMONITOR,
+    _hdc: HDC,
+    _rect: *mut RECT,
+    lparam: LPARAM,
+) -> BOOL {
+    let monitors = &mut *(lparam.0 as *mut Vec<Monitor>);
+
+    let mut info = MONITORINFOEXW {
+        monitorInfo: MONITORINFO {
+            cbSize: mem::size_of::<MONITORINFOEXW>() as u32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    if GetMonitorInfoW(
+        hmonitor,
+        &mut info as *mut MONITORINFOEXW as *mut MONITORINFO,
+    )
+    .as_bool()
+    {
+        let bounds = info.monitorInfo.rcMonitor;
+        let name_len = info
+            .szDevice
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(info.szDevice.len());
+
+        let (mut dpi, mut _dpi_y) = (DEFAULT_DPI, DEFAULT_DPI);
+        if GetDpiForMonitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi, &mut _dpi_y).is_err() {
+            dpi = DEFAULT_DPI;
+        }
+
+        monitors.push(Monitor {
+            name: String::from_utf16_lossy(&info.szDevice[..name_len]),
+            bounds: Rect {
+                x: bounds.left,
+                y: bounds.top,
+                width: bounds.right - bounds.left,
+                height: bounds.bottom - bounds.top,
+            },
+            primary: info.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+            dpi,
+        });
+    }
+
+    true.into()
+}
+
+/// Returns the DPI of the monitor the window is on.
+pub fn get_window_dpi(hwnd: HWND) -> u32 {
+    match unsafe { GetDpiForWindow(hwnd) } {
+        0 => DEFAULT_DPI,
+        dpi => dpi,
+    }
+}
+
+/// Moves and resizes the window to the given rect.
+pub fn set_window_rect(hwnd: HWND, rect: &Rect) -> Result<()> {
+    unsafe {
+        Ok(SetWindowPos(
+            hwnd,
+            None,
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            SWP_NOZORDER | SWP_NOACTIVATE,
+        )?)
+    }
+}
+
+/// Sets whether mouse input passes through the window to the windows behind it. This requires the
+/// window to be layered.
+pub fn set_input_transparent(hwnd: HWND, transparent: bool) {
+    let style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+    let new_style = match transparent {
+        true => style | WS_EX_TRANSPARENT.0,
+        false => style & !WS_EX_TRANSPARENT.0,
+    };
+
+    if new_style != style {
+        unsafe { SetWindowLongW(hwnd, GWL_EXSTYLE, new_style as i32) };
+    }
+}
+
+/// Returns the cursor position relative to the client area of the window.
+pub fn get_client_cursor_pos(hwnd: HWND) -> Result<(i32, i32)> {
+    let mut point = POINT::default();
+    unsafe {
+        GetCursorPos(&mut point)?;
+        ScreenToClient(hwnd, &mut point).ok()?;
+    }
+
+    Ok((point.x, point.y))
+}
+
+/// Returns the bounds of the window in screen coordinates.
+pub fn get_window_bounds(hwnd: HWND) -> Result<Rect> {
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(hwnd, &mut rect)? };
+
+    Ok(Rect {
+        x: rect.left,
+        y: rect.top,
+        width: rect.right - rect.left,
+        height: rect.bottom - rect.top,
+    })
+}
+
+/// Returns the foreground window, if there is one.
+pub fn get_foreground_window() -> Option<ForegroundWindow> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_invalid() {
+        return None;
+    }
+
+    let mut class = [0u16; 256];
+    let len = unsafe { GetClassNameW(hwnd, &mut class) };
+
+    Some(ForegroundWindow {
+        class: String::from_utf16_lossy(&class[..len.max(0) as usize]),
+        rect: get_window_bounds(hwnd).ok()?,
+        maximized: unsafe { IsZoomed(hwnd) }.as_bool(),
+    })
+}
+
+/// Registers a global hotkey, which posts `WM_HOTKEY` with the given id to the window. Holding
+/// the chord down does not repeat the hotkey.
+pub fn register_hotkey(hwnd: HWND, id: i32, chord: &Chord) -> windows::core::Result<()> {
+    unsafe {
+        RegisterHotKey(
+            hwnd,
+            id,
+            HOT_KEY_MODIFIERS(chord.modifiers) | MOD_NOREPEAT,
+            chord.key,
+        )
+    }
+}
+
+pub fn unregister_hotkey(hwnd: HWND, id: i32) {
+    let _ = unsafe { UnregisterHotKey(hwnd, id) };
+}